@@ -0,0 +1,119 @@
+use super::{field_name, structs::field_type};
+use crate::{Format, Item, Meta};
+use itertools::Itertools;
+use proc_macro_error::abort;
+use quote::{format_ident, quote, ToTokens};
+
+/// Generates a getter for every field when they're private, returning the elements of repeated fields as
+/// a slice and conditional fields as an `Option` of a reference
+pub(super) fn generate_getters(
+    items: &[Item],
+    meta: &Meta,
+    struct_name: &syn::Ident,
+) -> Vec<proc_macro2::TokenStream> {
+    if !meta.getters {
+        return Vec::new();
+    }
+
+    // getters share the namespace of the other generated methods
+    let methods = [
+        meta.read_fn.to_string(),
+        meta.write_fn.to_string(),
+        format!("try_{}", meta.read_fn),
+        "to_bytes".to_owned(),
+        "from_bytes".to_owned(),
+        "size_hint".to_owned(),
+    ];
+
+    items
+        .iter()
+        .map(|item| {
+            let field = field_name(item);
+            if methods.contains(&field.to_string()) {
+                abort!(
+                    struct_name,
+                    "getter for `{}` would clash with a generated method, so the field needs renaming",
+                    field
+                )
+            }
+
+            let data_type = item.enum_type.as_ref().unwrap_or(&item.data_type);
+            let (return_type, value) = match (&item.repetition, &item.condition) {
+                (Some(_), _) => (quote! { &[#data_type] }, quote! { &self.#field }),
+                (None, Some(_)) => (
+                    quote! { Option<&#data_type> },
+                    quote! { self.#field.as_ref() },
+                ),
+                (None, None) => (quote! { &#data_type }, quote! { &self.#field }),
+            };
+
+            quote! {
+                pub fn #field(&self) -> #return_type {
+                    #value
+                }
+            }
+        })
+        .collect()
+}
+
+/// Generates accessors which reach into nested composite fields, like `header_flags()` for `header.flags`
+pub(super) fn generate_flattened_accessors(
+    struct_name: &syn::Ident,
+    format: &Format,
+) -> proc_macro2::TokenStream {
+    let accessors = format.meta.flatten_accessors.iter().map(|path| {
+        // walk down the path, finding the item for each segment in the type of the previous one
+        let mut items = &format.items;
+        let mut item: Option<&Item> = None;
+        let mut fields = Vec::new();
+
+        for segment in path {
+            if let Some(parent) = item {
+                if parent.condition.is_some() || parent.repetition.is_some() {
+                    abort!(
+                        struct_name,
+                        "can't flatten through `{}` as it's conditional or repeated",
+                        parent.id
+                    )
+                }
+
+                let type_name = parent.data_type.to_token_stream().to_string();
+                items = format
+                    .types
+                    .iter()
+                    .find(|(name, _)| name.to_string() == type_name)
+                    .map(|(_, items)| items)
+                    .unwrap_or_else(|| {
+                        abort!(struct_name, "`{}` isn't a composite type", parent.id)
+                    });
+            }
+
+            item = Some(
+                items
+                    .iter()
+                    .find(|item| &item.id == segment)
+                    .unwrap_or_else(|| abort!(struct_name, "no field `{}` in path", segment)),
+            );
+            fields.extend(item.map(field_name));
+        }
+
+        let Some(item) = item else {
+            abort!(struct_name, "accessor paths can't be empty")
+        };
+
+        let fn_name = format_ident!("{}", path.iter().join("_"));
+        let data_type = field_type(item);
+
+        quote! {
+            pub fn #fn_name(&self) -> &#data_type {
+                &self.#(#fields).*
+            }
+        }
+    });
+
+    quote! {
+        impl #struct_name {
+            #(#accessors)*
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::{Format, Item};
+use proc_macro2::{TokenStream, TokenTree};
+use proc_macro_error::abort_call_site;
+use quote::ToTokens;
+use std::collections::HashMap;
+
+/// Replaces every use of an alias in a type's tokens with the type it stands for, looking inside groups
+/// so aliases can be used in arrays and tuples
+fn replace_aliases(tokens: TokenStream, aliases: &HashMap<syn::Ident, syn::Type>) -> TokenStream {
+    tokens
+        .into_iter()
+        .flat_map(|token| match token {
+            TokenTree::Ident(ident) => match aliases.get(&ident) {
+                Some(data_type) => data_type.to_token_stream(),
+                None => TokenTree::Ident(ident).into(),
+            },
+            TokenTree::Group(group) => {
+                let stream = replace_aliases(group.stream(), aliases);
+                let mut replaced = proc_macro2::Group::new(group.delimiter(), stream);
+                replaced.set_span(group.span());
+                TokenTree::Group(replaced).into()
+            }
+            token => token.into(),
+        })
+        .collect()
+}
+
+/// Resolves aliases in a type to the types they stand for
+fn resolve(data_type: &mut syn::Type, aliases: &HashMap<syn::Ident, syn::Type>) {
+    let tokens = replace_aliases(data_type.to_token_stream(), aliases);
+    *data_type = syn::parse2(tokens)
+        .unwrap_or_else(|_| abort_call_site!("aliases can only be used in place of a type"));
+}
+
+/// Resolves aliases in every type of an item
+fn resolve_item(item: &mut Item, aliases: &HashMap<syn::Ident, syn::Type>) {
+    resolve(&mut item.data_type, aliases);
+    if let Some(narrowing) = &mut item.narrowing {
+        resolve(&mut narrowing.data_type, aliases);
+    }
+}
+
+/// Resolves the type aliases defined in the `meta` section to their types, so items can be given a
+/// name meaningful to the format while still being read as the underlying type
+pub(super) fn resolve_aliases(format: &mut Format) {
+    if format.meta.aliases.is_empty() {
+        return;
+    }
+
+    let aliases = &format.meta.aliases;
+    let items = format.types.values_mut().chain([&mut format.items]);
+    for item in items.flatten() {
+        resolve_item(item, aliases);
+    }
+}
@@ -0,0 +1,60 @@
+use super::encoded_size;
+use crate::{Format, Item, Repetition};
+use quote::ToTokens;
+
+/// Finds the count of an item repeated a literal number of times
+fn literal_count(item: &Item) -> Option<&syn::LitInt> {
+    match &item.repetition {
+        Some(Repetition::Count(syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(count),
+            ..
+        }))) => Some(count),
+        _ => None,
+    }
+}
+
+/// Checks if a type is one of the types defined in the format, given by name
+fn is_format_type(data_type: &syn::Type, type_names: &[syn::Ident]) -> bool {
+    let data_type = data_type.to_token_stream().to_string();
+    type_names.iter().any(|name| *name == data_type)
+}
+
+/// Turns an item repeated a literal number of times into a fixed size array, if its elements have a
+/// fixed size or are defined in the format, and it doesn't need anything only repeated items support
+/// (like the element index).
+fn fix_literal_count(item: &mut Item, lengths: &[syn::Ident], type_names: &[syn::Ident]) {
+    let Some(count) = literal_count(item) else {
+        return;
+    };
+    if (encoded_size(&item.data_type).is_none() && !is_format_type(&item.data_type, type_names))
+        || item.big_endian_if.is_some()
+        || item.narrowing.is_some()
+        || item.layout.is_some()
+        || item.encoding.is_some()
+        || lengths.contains(&item.id)
+    {
+        return;
+    }
+
+    let data_type = &item.data_type;
+    item.data_type = syn::parse_quote! { [#data_type; #count] };
+    item.repetition = None;
+}
+
+/// Turns every item repeated a literal number of times into a fixed size array where possible, so no
+/// `Vec` is needed
+pub(super) fn fix_literal_counts(format: &mut Format) {
+    let type_names: Vec<_> = format.types.keys().cloned().collect();
+    let items = format.types.values_mut().chain([&mut format.items]);
+
+    for items in items {
+        // lengths are only written for repeated fields
+        let lengths: Vec<_> = items
+            .iter()
+            .filter_map(|item| item.length_of.clone())
+            .collect();
+        for item in items.iter_mut() {
+            fix_literal_count(item, &lengths, &type_names);
+        }
+    }
+}
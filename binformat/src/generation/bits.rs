@@ -0,0 +1,192 @@
+use super::{endianness_of, field_name};
+use crate::{parse::Endianness, BitField, Encoding, Item, Meta};
+use proc_macro_error::abort;
+use quote::{format_ident, quote, ToTokens};
+
+/// Gives the bit field an item is stored as, if it's one
+fn bit_field(item: &Item) -> Option<&BitField> {
+    match &item.encoding {
+        Some(Encoding::Bits(field)) => Some(field),
+        _ => None,
+    }
+}
+
+/// Gives the local holding an item's unit if the item is the first field in it, which is where the
+/// unit is read
+pub(super) fn starts_unit(item: &Item) -> Option<&syn::Ident> {
+    let field = bit_field(item)?;
+    (field.unit == format_ident!("_bits_{}", item.id)).then_some(&field.unit)
+}
+
+/// Finds the fields packed into a unit, starting from its lowest bits
+fn unit_fields<'a>(items: &'a [Item], unit: &syn::Ident) -> Vec<(&'a Item, u32)> {
+    items
+        .iter()
+        .filter_map(|item| match bit_field(item) {
+            Some(field) if &field.unit == unit => Some((item, field.width)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Number of bytes taken by the unit an item starts, or none if it isn't the first field of a unit
+pub(super) fn unit_size(item: &Item, items: &[Item]) -> Option<usize> {
+    let fields = unit_fields(items, starts_unit(item)?);
+    Some(
+        fields
+            .iter()
+            .map(|(_, width)| *width as usize)
+            .sum::<usize>()
+            / 8,
+    )
+}
+
+/// Number of bits in the type a bit field is stored as
+fn type_width(data_type: &syn::Type) -> u32 {
+    match data_type.to_token_stream().to_string().as_str() {
+        "bool" => 1,
+        "u8" => 8,
+        "u16" => 16,
+        "u32" => 32,
+        _ => 64,
+    }
+}
+
+/// Byteorder type for reading and writing a unit
+fn byte_order(item: &Item, meta: &Meta) -> proc_macro2::TokenStream {
+    match endianness_of(item, meta) {
+        Endianness::Little => quote! { ::byteorder::LittleEndian },
+        Endianness::Big => quote! { ::byteorder::BigEndian },
+    }
+}
+
+/// Makes sure bit fields aren't given options which need them to take whole bytes of their own.
+pub(super) fn validate_bits(items: &[Item], struct_name: &syn::Ident) {
+    for item in items {
+        if bit_field(item).is_none() {
+            continue;
+        }
+
+        if item.condition.is_some()
+            || item.repetition.is_some()
+            || item.reference.is_some()
+            || item.enum_type.is_some()
+            || item.big_endian_if.is_some()
+            || item.endianness.is_some()
+            || item.byteswap
+            || item.narrowing.is_some()
+            || item.length_of.is_some()
+            || item.pad_to.is_some()
+            || item.align.is_some()
+            || item.assert_len.is_some()
+        {
+            abort!(
+                struct_name,
+                "bit fields can't be combined with other options (on `{}`)",
+                item.id
+            )
+        }
+    }
+}
+
+/// Generates the read of a bit field from its unit, which is read first if the field starts it. The
+/// unit is kept in a local for the fields after it.
+pub(super) fn generate_bits_read(
+    item: &Item,
+    items: &[Item],
+    meta: &Meta,
+) -> proc_macro2::TokenStream {
+    let id = &item.id;
+    let data_type = &item.data_type;
+    let Some(field) = bit_field(item) else {
+        unreachable!("only called for bit fields")
+    };
+    let unit = &field.unit;
+
+    let read_unit = unit_size(item, items).map(|size| {
+        let byte_order = byte_order(item, meta);
+        quote! { let #unit = reader.read_uint::<#byte_order>(#size)?; }
+    });
+
+    let shift: u32 = unit_fields(items, unit)
+        .iter()
+        .take_while(|(other, _)| other.id != *id)
+        .map(|(_, width)| width)
+        .sum();
+    let mut value = quote! { #unit };
+    if shift > 0 {
+        value = quote! { (#value >> #shift) };
+    }
+    let value = match (field.width, type_width(data_type)) {
+        (1, 1) => quote! { #value & 1 != 0 },
+        (64, _) => value,
+        (width, type_width) => {
+            let mask = (1u64 << width) - 1;
+            let value = quote! { (#value & #mask) };
+            if type_width == 64 {
+                value
+            } else {
+                quote! { #value as #data_type }
+            }
+        }
+    };
+
+    quote! { #read_unit let #id = #value }
+}
+
+/// Generates the write of a whole unit, packing in every field from `self`, once the last field in it is
+/// reached. Values which don't fit in their width fail with `InvalidInput`, rather than spilling into the
+/// next field.
+pub(super) fn generate_bits_write(
+    item: &Item,
+    items: &[Item],
+    meta: &Meta,
+) -> Option<proc_macro2::TokenStream> {
+    let unit = &bit_field(item)?.unit;
+    let fields = unit_fields(items, unit);
+    if fields.last()?.0.id != item.id {
+        return None;
+    }
+
+    let first = fields.first()?.0;
+    let size = unit_size(first, items)?;
+    let byte_order = byte_order(first, meta);
+
+    let mut shift = 0;
+    let packs = fields.iter().map(|(field, width)| {
+        let id = &field.id;
+        let name = field_name(field);
+        let check = (*width < type_width(&field.data_type)).then(|| {
+            let mask = (1u64 << width) - 1;
+            quote! {
+                if value > #mask {
+                    return Err(::std::io::Error::new(
+                        ::std::io::ErrorKind::InvalidInput,
+                        concat!("`", stringify!(#id), "` doesn't fit in ", #width, " bits"),
+                    ));
+                }
+            }
+        });
+        let shifted = if shift > 0 {
+            quote! { value << #shift }
+        } else {
+            quote! { value }
+        };
+        shift += width;
+
+        quote! {
+            let value = u64::from(self.#name);
+            #check
+            unit |= #shifted;
+        }
+    });
+    let packs: Vec<_> = packs.collect();
+
+    Some(quote! {
+        (|| {
+            let mut unit = 0u64;
+            #(#packs)*
+            writer.write_uint::<#byte_order>(unit, #size)
+        })()
+    })
+}
@@ -0,0 +1,39 @@
+use super::{reads::is_swappable, validate_prior_integer};
+use crate::{Format, Item};
+use proc_macro_error::abort;
+
+/// Selects the byte order of the items from each `endian_of` onwards by the value of its field, with
+/// zero being little endian and anything else big endian. Items choosing their own with `big_endian_if`
+/// or `endian`, or whose type doesn't have a byte order, are left alone.
+fn resolve_items(items: &mut [Item], struct_name: &syn::Ident) {
+    let mut selector: Option<syn::Ident> = None;
+
+    for index in 0..items.len() {
+        if let Some(field) = &items[index].endian_of {
+            validate_prior_integer(items, index, field, "endian_of", struct_name);
+            selector = Some(field.clone());
+        }
+
+        let item = &mut items[index];
+        if item.endianness.is_some() && item.big_endian_if.is_some() {
+            abort!(
+                struct_name,
+                "endian can't be combined with big_endian_if (on `{}`)",
+                item.id
+            )
+        }
+        if let (Some(field), None, None) = (&selector, &item.big_endian_if, &item.endianness) {
+            if is_swappable(&item.data_type) {
+                item.big_endian_if = Some(syn::parse_quote! { #field != 0 });
+            }
+        }
+    }
+}
+
+/// Resolves every `endian_of` in the format into the `big_endian_if` of the items it applies to
+pub(super) fn resolve_endian_of(format: &mut Format, root_name: &syn::Ident) {
+    for (name, items) in format.types.iter_mut() {
+        resolve_items(items, name);
+    }
+    resolve_items(&mut format.items, root_name);
+}
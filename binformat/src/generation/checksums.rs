@@ -0,0 +1,343 @@
+use super::{endianness_of, footers::footer_item, Method};
+use crate::{parse::Endianness, Checksum, ChecksumRange, Encoding, Item, Meta};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+/// Number of bytes taken by the checksum trailing the root struct
+pub(super) fn checksum_size(meta: &Meta) -> usize {
+    match meta.checksum {
+        Some(Checksum::Crc32) => 4,
+        None => 0,
+    }
+}
+
+/// Generates the crc32 update shared by reads and writes, folding `bytes` into `crc`
+fn crc32_step() -> proc_macro2::TokenStream {
+    quote! {
+        for byte in bytes {
+            crc ^= *byte as u32;
+            for _ in 0..8 {
+                crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb8_8320 } else { crc >> 1 };
+            }
+        }
+    }
+}
+
+/// Byteorder type matching an endianness, for reading and writing checksums
+fn byte_order(endianness: Endianness) -> proc_macro2::TokenStream {
+    match endianness {
+        Endianness::Little => quote! { ::byteorder::LittleEndian },
+        Endianness::Big => quote! { ::byteorder::BigEndian },
+    }
+}
+
+/// Generates code wrapping the reader so the checksum is worked out over every byte read.
+///
+/// The running checksum is kept in `_crc` outside the wrapper, so it can still be reached if the
+/// reader is wrapped again.
+pub(super) fn generate_checksummed_reader(meta: &Meta) -> Option<proc_macro2::TokenStream> {
+    meta.checksum.as_ref()?;
+    let step = crc32_step();
+
+    Some(quote! {
+        use ::std::io::Read as _;
+        use ::byteorder::ReadBytesExt as _;
+
+        struct CrcReader<'a, R> {
+            inner: &'a mut R,
+            crc: &'a ::std::cell::Cell<u32>,
+        }
+
+        impl<R: ::std::io::Read> ::std::io::Read for CrcReader<'_, R> {
+            fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+                let read = self.inner.read(buf)?;
+                let mut crc = self.crc.get();
+                let bytes = &buf[..read];
+                #step
+                self.crc.set(crc);
+                Ok(read)
+            }
+        }
+
+        let _crc = ::std::cell::Cell::new(0xffff_ffff);
+        let reader = &mut CrcReader {
+            inner: &mut *reader,
+            crc: &_crc,
+        };
+    })
+}
+
+/// Generates a check that the trailing checksum matches the bytes read
+pub(super) fn generate_checksum_check(meta: &Meta) -> Option<proc_macro2::TokenStream> {
+    meta.checksum.as_ref()?;
+    let byte_order = byte_order(meta.endianness);
+
+    Some(quote! {
+        let crc = !_crc.get();
+        if reader.read_u32::<#byte_order>()? != crc {
+            return Err(::std::io::Error::new(
+                ::std::io::ErrorKind::InvalidData,
+                "crc32 doesn't match the data",
+            ));
+        }
+    })
+}
+
+/// Generates code wrapping the writer so the checksum is worked out over every byte written, kept in `_crc`
+pub(super) fn generate_checksummed_writer(meta: &Meta) -> Option<proc_macro2::TokenStream> {
+    meta.checksum.as_ref()?;
+    let step = crc32_step();
+
+    Some(quote! {
+        use ::std::io::Write as _;
+        use ::byteorder::WriteBytesExt as _;
+
+        struct CrcWriter<'a, W> {
+            inner: &'a mut W,
+            crc: &'a ::std::cell::Cell<u32>,
+        }
+
+        impl<W: ::std::io::Write> ::std::io::Write for CrcWriter<'_, W> {
+            fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+                let written = self.inner.write(buf)?;
+                let mut crc = self.crc.get();
+                let bytes = &buf[..written];
+                #step
+                self.crc.set(crc);
+                Ok(written)
+            }
+
+            fn flush(&mut self) -> ::std::io::Result<()> {
+                self.inner.flush()
+            }
+        }
+
+        let _crc = ::std::cell::Cell::new(0xffff_ffff);
+        let writer = &mut CrcWriter {
+            inner: &mut *writer,
+            crc: &_crc,
+        };
+    })
+}
+
+/// Generates the write of the trailing checksum over every byte written
+pub(super) fn generate_checksum_write(meta: &Meta) -> Option<proc_macro2::TokenStream> {
+    meta.checksum.as_ref()?;
+    let byte_order = byte_order(meta.endianness);
+
+    Some(quote! {
+        let crc = !_crc.get();
+        writer.write_u32::<#byte_order>(crc)?;
+    })
+}
+
+/// Finds the range of bytes covered by a checksum field
+fn checksum_range(item: &Item) -> Option<&ChecksumRange> {
+    match &item.encoding {
+        Some(Encoding::Checksum(range)) => Some(range),
+        _ => None,
+    }
+}
+
+/// Makes sure checksum fields are plain fields of the root struct, with a range which isn't empty
+pub(super) fn validate_checksum_fields(items: &[Item], struct_name: &syn::Ident, is_root: bool) {
+    for item in items {
+        let Some(range) = checksum_range(item) else {
+            continue;
+        };
+
+        // the range is counted from the start of the data, which nested structs don't know
+        if !is_root {
+            abort!(
+                struct_name,
+                "checksum fields can only be used in the root struct (on `{}`)",
+                item.id
+            )
+        }
+        if item.condition.is_some()
+            || item.repetition.is_some()
+            || item.reference.is_some()
+            || item.enum_type.is_some()
+            || item.big_endian_if.is_some()
+            || item.narrowing.is_some()
+            || item.length_of.is_some()
+        {
+            abort!(struct_name, "checksum `{}` must be a plain field", item.id)
+        }
+        // footers are read by seeking, which the checksum couldn't follow
+        if footer_item(items).is_some() {
+            abort!(
+                struct_name,
+                "checksum fields can't be combined with footer lengths (on `{}`)",
+                item.id
+            )
+        }
+        if range.end.is_some_and(|end| end <= range.start) {
+            abort!(struct_name, "range of checksum `{}` is empty", item.id)
+        }
+    }
+}
+
+/// Names of the running checksum of a checksum field and the position of the data it has reached
+fn range_state(item: &Item) -> (syn::Ident, syn::Ident) {
+    (
+        format_ident!("_crc_{}", item.id),
+        format_ident!("_position_{}", item.id),
+    )
+}
+
+/// Generates code wrapping the reader or writer once for each checksum field, so its checksum is worked
+/// out over the bytes in its range as they're read or written
+pub(super) fn generate_checksum_ranges(
+    items: &[Item],
+    method: Method,
+) -> Option<proc_macro2::TokenStream> {
+    let fields: Vec<_> = items
+        .iter()
+        .filter_map(|item| Some((item, checksum_range(item)?)))
+        .collect();
+    if fields.is_empty() {
+        return None;
+    }
+
+    let step = crc32_step();
+    let wrappers = fields.iter().map(|(item, range)| {
+        let (crc, position) = range_state(item);
+        let start = range.start;
+        let end = range.end.unwrap_or(usize::MAX);
+        let stream = match method {
+            Method::Reading => quote! { reader },
+            Method::Writing => quote! { writer },
+        };
+
+        quote! {
+            let #crc = ::std::cell::Cell::new(0xffff_ffff_u32);
+            let #position = ::std::cell::Cell::new(0_usize);
+            let #stream = &mut CrcRange {
+                inner: &mut *#stream,
+                crc: &#crc,
+                position: &#position,
+                range: #start..#end,
+            };
+        }
+    });
+
+    Some(quote! {
+        use ::std::io::{Read as _, Write as _};
+        use ::byteorder::{ReadBytesExt as _, WriteBytesExt as _};
+
+        struct CrcRange<'a, T> {
+            inner: &'a mut T,
+            crc: &'a ::std::cell::Cell<u32>,
+            position: &'a ::std::cell::Cell<usize>,
+            range: ::std::ops::Range<usize>,
+        }
+
+        impl<T> CrcRange<'_, T> {
+            /// Folds the bytes which are in range into the checksum, moving past all of them
+            fn update(&self, buf: &[u8]) {
+                let position = self.position.get();
+                let start = self.range.start.saturating_sub(position).min(buf.len());
+                let end = self.range.end.saturating_sub(position).clamp(start, buf.len());
+
+                let mut crc = self.crc.get();
+                let bytes = &buf[start..end];
+                #step
+                self.crc.set(crc);
+                self.position.set(position + buf.len());
+            }
+        }
+
+        impl<R: ::std::io::Read> ::std::io::Read for CrcRange<'_, R> {
+            fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+                let read = self.inner.read(buf)?;
+                self.update(&buf[..read]);
+                Ok(read)
+            }
+        }
+
+        impl<W: ::std::io::Write> ::std::io::Write for CrcRange<'_, W> {
+            fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+                let written = self.inner.write(buf)?;
+                self.update(&buf[..written]);
+                Ok(written)
+            }
+
+            fn flush(&mut self) -> ::std::io::Result<()> {
+                self.inner.flush()
+            }
+        }
+
+        #(#wrappers)*
+    })
+}
+
+/// Generates a check that a checksum field's range ends before it, as the checksum is needed when the
+/// field is reached
+fn generate_range_check(
+    item: &Item,
+    range: &ChecksumRange,
+    kind: proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    let end = range.end?;
+    let id = &item.id;
+    let (_, position) = range_state(item);
+
+    Some(quote! {
+        if #position.get() < #end {
+            return Err(::std::io::Error::new(
+                ::std::io::ErrorKind::#kind,
+                concat!("range of checksum `", stringify!(#id), "` ends after it"),
+            ));
+        }
+    })
+}
+
+/// Generates the read of a checksum field, which fails if it doesn't match the bytes in its range
+pub(super) fn generate_checksum_field_read(item: &Item, meta: &Meta) -> proc_macro2::TokenStream {
+    let Some(range) = checksum_range(item) else {
+        unreachable!("only called for checksum fields")
+    };
+    let id = &item.id;
+    let (crc, _) = range_state(item);
+    let byte_order = byte_order(endianness_of(item, meta));
+    let range_check = generate_range_check(item, range, quote! { InvalidData });
+    let read = match range.checksum {
+        Checksum::Crc32 => quote! { reader.read_u32::<#byte_order>() },
+    };
+
+    quote! {
+        (|| {
+            #range_check
+            let expected = !#crc.get();
+            match #read? {
+                crc if crc == expected => Ok(crc),
+                _ => Err(::std::io::Error::new(
+                    ::std::io::ErrorKind::InvalidData,
+                    concat!("checksum `", stringify!(#id), "` doesn't match the data"),
+                )),
+            }
+        })()
+    }
+}
+
+/// Generates the write of a checksum field, working it out from the bytes written in its range rather
+/// than using the stored value
+pub(super) fn generate_checksum_field_write(item: &Item, meta: &Meta) -> proc_macro2::TokenStream {
+    let Some(range) = checksum_range(item) else {
+        unreachable!("only called for checksum fields")
+    };
+    let (crc, _) = range_state(item);
+    let byte_order = byte_order(endianness_of(item, meta));
+    let range_check = generate_range_check(item, range, quote! { InvalidInput });
+    let write = match range.checksum {
+        Checksum::Crc32 => quote! { writer.write_u32::<#byte_order>(!#crc.get()) },
+    };
+
+    quote! {
+        (|| {
+            #range_check
+            #write
+        })()
+    }
+}
@@ -0,0 +1,75 @@
+use crate::{Format, Item, Predicate, Repetition};
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use proc_macro_error::abort_call_site;
+use quote::ToTokens;
+use std::collections::HashMap;
+
+/// Replaces every use of a constant in a token stream with its value, looking inside groups
+fn replace_constants(
+    tokens: TokenStream,
+    constants: &HashMap<syn::Ident, syn::Expr>,
+) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            // values are wrapped in an invisible group, so negative numbers stay together
+            TokenTree::Ident(ident) => match constants.get(&ident) {
+                Some(value) => {
+                    TokenTree::Group(Group::new(Delimiter::None, value.to_token_stream()))
+                }
+                None => TokenTree::Ident(ident),
+            },
+            TokenTree::Group(group) => {
+                let stream = replace_constants(group.stream(), constants);
+                let mut replaced = Group::new(group.delimiter(), stream);
+                replaced.set_span(group.span());
+                TokenTree::Group(replaced)
+            }
+            token => token,
+        })
+        .collect()
+}
+
+/// Substitutes constants into a parsed expression, which only fails if a constant is used somewhere
+/// other than as a value, such as a field name in a path
+fn substitute<T: syn::parse::Parse + ToTokens>(
+    value: &mut T,
+    constants: &HashMap<syn::Ident, syn::Expr>,
+) {
+    let tokens = replace_constants(value.to_token_stream(), constants);
+    *value = syn::parse2(tokens).unwrap_or_else(|_| {
+        abort_call_site!("constants can only be used as values, so can't share a name with a field")
+    });
+}
+
+/// Substitutes constants into every expression of an item
+fn substitute_item(item: &mut Item, constants: &HashMap<syn::Ident, syn::Expr>) {
+    if let Some(condition) = &mut item.condition {
+        if let Predicate::Expression(expr) = &mut condition.predicate {
+            substitute(&mut **expr, constants);
+        }
+    }
+    if let Some(Repetition::Count(expr) | Repetition::Until(expr)) = &mut item.repetition {
+        substitute(expr, constants);
+    }
+    if let Some(expr) = &mut item.big_endian_if {
+        substitute(expr, constants);
+    }
+    if let Some(narrowing) = &mut item.narrowing {
+        substitute(&mut narrowing.expression, constants);
+    }
+}
+
+/// Substitutes the constants defined in the `meta` section into the expressions of every item, so
+/// conditions and counts can use names rather than bare numbers
+pub(super) fn substitute_constants(format: &mut Format) {
+    if format.meta.constants.is_empty() {
+        return;
+    }
+
+    let constants = &format.meta.constants;
+    let items = format.types.values_mut().chain([&mut format.items]);
+    for item in items.flatten() {
+        substitute_item(item, constants);
+    }
+}
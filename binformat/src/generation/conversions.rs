@@ -0,0 +1,63 @@
+use super::{declarations::generate_extra_inits, field_name, structs::field_type};
+use crate::{Conversion, Item};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Generates a `From` implementation converting the source format into this one, copying fields
+/// which exist in both with the same type and using the given defaults for the rest. Fields declared on
+/// the struct but not in the format are given their default value.
+pub(super) fn generate_conversion(
+    root: &syn::ItemStruct,
+    items: &[Item],
+    conversion: &Conversion,
+) -> proc_macro2::TokenStream {
+    let struct_name = &root.ident;
+    let extra_inits = generate_extra_inits(root, items);
+    let Conversion {
+        source,
+        source_items,
+        defaults,
+        ..
+    } = conversion;
+
+    let fields = items.iter().map(|item| {
+        let id = &item.id;
+        let field = field_name(item);
+
+        // explicit defaults take priority over copying, so fields can be reset on conversion
+        if let Some(default) = defaults.get(id) {
+            return quote! { #field: #default };
+        }
+
+        let data_type = field_type(item).to_string();
+        match source_items.iter().find(|other| &other.id == id) {
+            Some(other) if field_type(other).to_string() == data_type => {
+                let source_field = field_name(other);
+                quote! { #field: value.#source_field }
+            }
+            Some(_) => abort!(
+                struct_name,
+                "field `{}` has a different type in `{}`, so needs a default",
+                id,
+                source
+            ),
+            None => abort!(
+                struct_name,
+                "field `{}` doesn't exist in `{}`, so needs a default",
+                id,
+                source
+            ),
+        }
+    });
+
+    quote! {
+        impl ::std::convert::From<#source> for #struct_name {
+            fn from(value: #source) -> Self {
+                Self {
+                    #(#fields,)*
+                    #(#extra_inits),*
+                }
+            }
+        }
+    }
+}
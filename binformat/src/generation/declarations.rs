@@ -0,0 +1,103 @@
+use super::field_name;
+use crate::{Item, Meta};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Finds the fields declared on the annotated struct, if it has any. A unit struct has the macro
+/// generate its fields, while a struct with named fields keeps its own definition.
+pub(super) fn declared_fields(root: &syn::ItemStruct) -> Option<&syn::FieldsNamed> {
+    match &root.fields {
+        syn::Fields::Named(fields) => Some(fields),
+        syn::Fields::Unit => None,
+        syn::Fields::Unnamed(fields) => abort!(
+            fields,
+            "fields of `{}` need names to be matched with the format",
+            root.ident
+        ),
+    }
+}
+
+/// Makes sure every field of the format is declared on the annotated struct, if it declares its fields
+pub(super) fn validate_declared_fields(root: &syn::ItemStruct, items: &[Item]) {
+    let Some(fields) = declared_fields(root) else {
+        return;
+    };
+
+    for item in items {
+        let field = field_name(item);
+        if !fields
+            .named
+            .iter()
+            .any(|declared| declared.ident.as_ref() == Some(field))
+        {
+            abort!(
+                root.ident,
+                "field `{}` of the format isn't declared on `{}`",
+                field,
+                root.ident
+            )
+        }
+    }
+}
+
+/// Makes sure the annotated struct doesn't derive a trait the macro implements for it, as a struct declaring
+/// its own fields keeps its own derives
+pub(super) fn validate_declared_derives(root: &syn::ItemStruct, meta: &Meta) {
+    if declared_fields(root).is_none() {
+        return;
+    }
+
+    let derives = root
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("derive"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten();
+
+    for derive in derives {
+        let syn::NestedMeta::Meta(syn::Meta::Path(path)) = &derive else {
+            continue;
+        };
+        let Some(name) = path.segments.last().map(|segment| &segment.ident) else {
+            continue;
+        };
+
+        if (name == "Default" && meta.default) || (name == "PartialEq" && meta.bitwise_eq) {
+            abort!(
+                path,
+                "`{}` is implemented by the format for `{}`, so it can't be derived too",
+                name,
+                root.ident
+            )
+        }
+    }
+}
+
+/// Finds the fields declared on the annotated struct which aren't in the format
+pub(super) fn extra_fields<'a>(root: &'a syn::ItemStruct, items: &[Item]) -> Vec<&'a syn::Ident> {
+    let Some(fields) = declared_fields(root) else {
+        return Vec::new();
+    };
+
+    fields
+        .named
+        .iter()
+        .filter_map(|declared| declared.ident.as_ref())
+        .filter(|declared| !items.iter().any(|item| field_name(item) == *declared))
+        .collect()
+}
+
+/// Generates initialisers for fields declared on the annotated struct which aren't in the format, which
+/// are given their default value when reading
+pub(super) fn generate_extra_inits(
+    root: &syn::ItemStruct,
+    items: &[Item],
+) -> Vec<proc_macro2::TokenStream> {
+    extra_fields(root, items)
+        .into_iter()
+        .map(|declared| quote! { #declared: ::std::default::Default::default() })
+        .collect()
+}
@@ -0,0 +1,101 @@
+use super::{array_len, field_name};
+use crate::{Item, Meta};
+use quote::quote;
+
+/// Longest array the standard library implements `Default` for
+const MAX_DEFAULT_ARRAY_LEN: usize = 32;
+
+/// Checks if a type contains an array too long to implement `Default`, so can't have it derived
+fn needs_manual_default(data_type: &syn::Type) -> bool {
+    match data_type {
+        syn::Type::Array(array) => {
+            array_len(array).is_none_or(|len| len > MAX_DEFAULT_ARRAY_LEN)
+                || needs_manual_default(&array.elem)
+        }
+        syn::Type::Tuple(tuple) => tuple.elems.iter().any(needs_manual_default),
+        _ => false,
+    }
+}
+
+/// Generates the default value of a type, zero-initialising arrays which are too long to implement
+/// `Default` by filling them with their element's default
+pub(super) fn generate_default_value(data_type: &syn::Type) -> proc_macro2::TokenStream {
+    match data_type {
+        syn::Type::Array(array) if needs_manual_default(data_type) => {
+            let value = generate_default_value(&array.elem);
+            let len = &array.len;
+            quote! { ::std::array::from_fn::<_, #len, _>(|_| #value) }
+        }
+        syn::Type::Tuple(tuple) if needs_manual_default(data_type) => {
+            let values = tuple.elems.iter().map(generate_default_value);
+            quote! { (#(#values,)*) }
+        }
+        _ => quote! { <#data_type as ::std::default::Default>::default() },
+    }
+}
+
+/// Generates the default value of the field generated for an item
+pub(super) fn generate_field_default(item: &Item) -> proc_macro2::TokenStream {
+    if item.repetition.is_some() || item.condition.is_some() || item.enum_type.is_some() {
+        quote! { ::std::default::Default::default() }
+    } else {
+        generate_default_value(&item.data_type)
+    }
+}
+
+/// Checks if any field is stored as a type which can't derive `Default`, rather than being wrapped in a
+/// `Vec` or `Option`
+fn any_manual_default(items: &[Item]) -> bool {
+    items.iter().any(|item| {
+        item.repetition.is_none()
+            && item.condition.is_none()
+            && item.enum_type.is_none()
+            && needs_manual_default(&item.data_type)
+    })
+}
+
+/// Derives `Default` if enabled and every field supports it
+pub(super) fn generate_default_derive(
+    items: &[Item],
+    meta: &Meta,
+) -> Option<proc_macro2::TokenStream> {
+    (meta.default && !any_manual_default(items)).then(|| quote! { #[derive(Default)] })
+}
+
+/// Implements `Default` by hand if enabled and a field has an array too long for it to be derived. A
+/// struct declaring its own fields (given with those not in the format) always has it implemented by hand,
+/// as it keeps its own derives.
+pub(super) fn generate_default_impl(
+    struct_name: &syn::Ident,
+    items: &[Item],
+    meta: &Meta,
+    extra_fields: Option<&[&syn::Ident]>,
+) -> Option<proc_macro2::TokenStream> {
+    if !meta.default || (extra_fields.is_none() && !any_manual_default(items)) {
+        return None;
+    }
+
+    let extra = extra_fields.unwrap_or_default();
+    let fields = items
+        .iter()
+        .map(|item| {
+            let id = field_name(item);
+            let value = generate_field_default(item);
+            quote! { #id: #value }
+        })
+        .chain(
+            extra
+                .iter()
+                .map(|id| quote! { #id: ::std::default::Default::default() }),
+        );
+
+    Some(quote! {
+        impl ::std::default::Default for #struct_name {
+            fn default() -> Self {
+                Self {
+                    #(#fields),*
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,257 @@
+use super::{
+    field_name_of,
+    unions::{generate_union_read, generate_union_write},
+    validate_prior_integer,
+};
+use crate::{Encoding, Item, Meta};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Makes sure the length of every length-prefixed string is a plain integer read earlier in the same
+/// struct.
+pub(super) fn validate_strings(items: &[Item], struct_name: &syn::Ident) {
+    for (index, item) in items.iter().enumerate() {
+        if let Some(Encoding::LengthPrefixedString(len)) = &item.encoding {
+            validate_prior_integer(items, index, len, "len", struct_name);
+        }
+    }
+}
+
+/// Makes sure varints aren't given options which depend on a fixed width, as they take a varying number
+/// of bytes and have no byte order.
+pub(super) fn validate_varints(items: &[Item], struct_name: &syn::Ident) {
+    for item in items {
+        if !matches!(item.encoding, Some(Encoding::Varint | Encoding::Varuint)) {
+            continue;
+        }
+
+        let advance_if_false = item
+            .condition
+            .as_ref()
+            .is_some_and(|condition| condition.advance_if_false);
+        if advance_if_false || item.byteswap || item.narrowing.is_some() {
+            abort!(
+                struct_name,
+                "varints can't be combined with advance_if_false, byteswap or narrow_type (on `{}`)",
+                item.id
+            )
+        }
+    }
+}
+
+/// Makes sure computed fields aren't given options which only make sense for data in the byte stream.
+pub(super) fn validate_computed(items: &[Item], struct_name: &syn::Ident) {
+    for item in items {
+        if !matches!(item.encoding, Some(Encoding::Computed(_))) {
+            continue;
+        }
+
+        let advance_if_false = item
+            .condition
+            .as_ref()
+            .is_some_and(|condition| condition.advance_if_false);
+        if advance_if_false
+            || item.repetition.is_some()
+            || item.reference.is_some()
+            || item.enum_type.is_some()
+            || item.big_endian_if.is_some()
+            || item.endianness.is_some()
+            || item.byteswap
+            || item.narrowing.is_some()
+            || item.length_of.is_some()
+        {
+            abort!(
+                struct_name,
+                "computed fields can only be combined with `if` (on `{}`)",
+                item.id
+            )
+        }
+    }
+}
+
+/// Generates a read of a LEB128 integer, taking 7 bits from each byte until one without its top bit set.
+/// Values which don't fit in 64 bits are rejected.
+fn generate_varint_read(signed: bool) -> proc_macro2::TokenStream {
+    // the tenth byte only holds the last bit, which signed values extend into the rest of the byte - and it
+    // has to be the last byte
+    let (overflows, value) = if signed {
+        (
+            quote! { !matches!(byte & 0x7f, 0 | 0x7f) },
+            quote! {
+                if shift < 64 && byte & 0x40 != 0 {
+                    value |= !0 << shift;
+                }
+                value as i64
+            },
+        )
+    } else {
+        (quote! { byte & 0x7f > 1 }, quote! { value })
+    };
+
+    quote! {
+        (|| {
+            let mut value = 0u64;
+            let mut shift = 0;
+            let mut byte;
+
+            loop {
+                byte = reader.read_u8()?;
+                if shift == 63 && (byte & 0x80 != 0 || #overflows) {
+                    return Err(::std::io::Error::new(
+                        ::std::io::ErrorKind::InvalidData,
+                        "varint doesn't fit in 64 bits",
+                    ));
+                }
+
+                value |= u64::from(byte & 0x7f) << shift;
+                shift += 7;
+                if byte & 0x80 == 0 {
+                    break;
+                }
+            }
+
+            ::std::io::Result::Ok({ #value })
+        })()
+    }
+}
+
+/// Generates a write of a LEB128 integer, writing 7 bits at a time until the rest of the value is implied
+fn generate_varint_write(id: &proc_macro2::TokenStream, signed: bool) -> proc_macro2::TokenStream {
+    // signed values are done once the rest is all copies of the sign bit, which is the top bit of the group
+    let (data_type, done) = if signed {
+        (
+            quote! { i64 },
+            quote! { (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0) },
+        )
+    } else {
+        (quote! { u64 }, quote! { value == 0 })
+    };
+
+    quote! {
+        (|| {
+            let mut value: #data_type = #id;
+
+            loop {
+                let byte = (value & 0x7f) as u8;
+                value >>= 7;
+                if #done {
+                    break writer.write_u8(byte);
+                }
+                writer.write_u8(byte | 0x80)?;
+            }
+        })()
+    }
+}
+
+/// Generates a read for a type with a custom encoding, evaluating to an `io::Result`
+pub(super) fn generate_encoded_read(encoding: &Encoding, meta: &Meta) -> proc_macro2::TokenStream {
+    match encoding {
+        Encoding::DelimitedString(terminator) => {
+            // read a byte at a time so the terminator is found wherever it falls, without reading past it
+            quote! {
+                {
+                    let terminator: &[u8] = &[#(#terminator),*];
+                    let mut bytes = ::std::vec::Vec::new();
+
+                    loop {
+                        match reader.read_u8() {
+                            Ok(byte) => bytes.push(byte),
+                            Err(error) => break Err(error),
+                        }
+
+                        if bytes.ends_with(terminator) {
+                            bytes.truncate(bytes.len() - terminator.len());
+                            break ::std::string::String::from_utf8(bytes).map_err(|error| {
+                                ::std::io::Error::new(::std::io::ErrorKind::InvalidData, error)
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        // the length could be corrupt, so only as many bytes as are actually there are allocated
+        Encoding::LengthPrefixedString(len) => quote! {
+            (|| {
+                let length = #len as u64;
+                let mut bytes = ::std::vec::Vec::new();
+                ::std::io::Read::read_to_end(&mut ::std::io::Read::take(&mut *reader, length), &mut bytes)?;
+                if (bytes.len() as u64) < length {
+                    return Err(::std::io::ErrorKind::UnexpectedEof.into());
+                }
+
+                ::std::string::String::from_utf8(bytes).map_err(|error| {
+                    ::std::io::Error::new(::std::io::ErrorKind::InvalidData, error)
+                })
+            })()
+        },
+        Encoding::TaggedUnion(union) => generate_union_read(union, meta),
+        Encoding::Varint => generate_varint_read(true),
+        Encoding::Varuint => generate_varint_read(false),
+        Encoding::Computed(expr) => quote! { ::std::io::Result::Ok(#expr) },
+        Encoding::Bits(_) => unreachable!("bit fields are read with their unit"),
+        Encoding::Checksum(_) => unreachable!("checksum fields are read with their range"),
+    }
+}
+
+/// Generates a write for a type with a custom encoding, evaluating to an `io::Result`
+pub(super) fn generate_encoded_write(
+    id: &proc_macro2::TokenStream,
+    encoding: &Encoding,
+    item: &Item,
+    items: &[Item],
+    meta: &Meta,
+) -> proc_macro2::TokenStream {
+    match encoding {
+        Encoding::DelimitedString(terminator) => {
+            // a string containing the terminator would be cut short when read back, so refuse to write it
+            quote! {
+                {
+                    let terminator: &[u8] = &[#(#terminator),*];
+                    let bytes = #id.as_bytes();
+
+                    if bytes.windows(terminator.len()).any(|window| window == terminator) {
+                        Err(::std::io::Error::new(
+                            ::std::io::ErrorKind::InvalidInput,
+                            "string contains its terminator",
+                        ))
+                    } else {
+                        writer.write_all(bytes).and_then(|_| writer.write_all(terminator))
+                    }
+                }
+            }
+        }
+        Encoding::LengthPrefixedString(len) => {
+            // a length written from the string itself always matches, otherwise the stored one has to
+            let target = &item.id;
+            let from_target = items
+                .iter()
+                .any(|other| &other.id == len && other.length_of.as_ref() == Some(target));
+            if from_target {
+                return quote! { writer.write_all(#id.as_bytes()) };
+            }
+
+            let field = field_name_of(items, len);
+            quote! {
+                {
+                    let bytes = #id.as_bytes();
+
+                    if bytes.len() as u64 != self.#field as u64 {
+                        Err(::std::io::Error::new(
+                            ::std::io::ErrorKind::InvalidInput,
+                            concat!("`", stringify!(#target), "` doesn't match its length `", stringify!(#len), "`"),
+                        ))
+                    } else {
+                        writer.write_all(bytes)
+                    }
+                }
+            }
+        }
+        Encoding::TaggedUnion(union) => generate_union_write(id, union, item, items, meta),
+        Encoding::Varint => generate_varint_write(id, true),
+        Encoding::Varuint => generate_varint_write(id, false),
+        // computed fields are only stored in the struct, so there's nothing to write
+        Encoding::Computed(_) => quote! { ::std::io::Result::Ok(()) },
+        Encoding::Bits(_) => unreachable!("bit fields are written with their unit"),
+        Encoding::Checksum(_) => unreachable!("checksum fields are written from their range"),
+    }
+}
@@ -0,0 +1,108 @@
+use super::field_name;
+use crate::{Item, Meta};
+use quote::{quote, ToTokens};
+
+/// Checks whether a type has a float anywhere in it, which is the only case needing a bitwise comparison
+fn contains_float(data_type: &syn::Type) -> bool {
+    match data_type {
+        syn::Type::Array(array) => contains_float(&array.elem),
+        syn::Type::Tuple(tuple) => tuple.elems.iter().any(contains_float),
+        syn::Type::Paren(paren) => contains_float(&paren.elem),
+        _ => matches!(
+            data_type.to_token_stream().to_string().as_str(),
+            "f32" | "f64"
+        ),
+    }
+}
+
+/// Generates a comparison of two values of the given type (or references to them), comparing floats by
+/// their bits
+fn generate_comparison(
+    lhs: proc_macro2::TokenStream,
+    rhs: proc_macro2::TokenStream,
+    data_type: &syn::Type,
+) -> proc_macro2::TokenStream {
+    if !contains_float(data_type) {
+        return quote! { #lhs == #rhs };
+    }
+
+    match data_type {
+        syn::Type::Array(array) => {
+            let element = generate_comparison(quote! { a }, quote! { b }, &array.elem);
+            quote! { #lhs.iter().zip(#rhs.iter()).all(|(a, b)| #element) }
+        }
+        syn::Type::Tuple(tuple) => {
+            let elements = (0..tuple.elems.len()).map(syn::Index::from);
+            let comparisons = tuple.elems.iter().zip(elements).map(|(elem, index)| {
+                generate_comparison(quote! { #lhs.#index }, quote! { #rhs.#index }, elem)
+            });
+            quote! { (#(#comparisons)&&*) }
+        }
+        syn::Type::Paren(paren) => generate_comparison(lhs, rhs, &paren.elem),
+        _ => quote! { #lhs.to_bits() == #rhs.to_bits() },
+    }
+}
+
+/// Generates the comparison of one field, wrapped the same way as its type in the struct
+fn generate_field_comparison(item: &Item) -> proc_macro2::TokenStream {
+    let id = field_name(item);
+
+    // enums are stored as the resolved value rather than the index
+    let data_type = item.enum_type.as_ref().unwrap_or(&item.data_type);
+    if !contains_float(data_type) {
+        return quote! { self.#id == other.#id };
+    }
+
+    let element = generate_comparison(quote! { a }, quote! { b }, data_type);
+    match (&item.repetition, &item.condition) {
+        (Some(_), _) => quote! {
+            (self.#id.len() == other.#id.len()
+                && self.#id.iter().zip(other.#id.iter()).all(|(a, b)| #element))
+        },
+        (None, Some(_)) => quote! {
+            (match (&self.#id, &other.#id) {
+                (Some(a), Some(b)) => #element,
+                (None, None) => true,
+                _ => false,
+            })
+        },
+        _ => generate_comparison(quote! { self.#id }, quote! { other.#id }, data_type),
+    }
+}
+
+/// Implements `PartialEq` by hand if `bitwise_eq` is enabled, comparing floats by their bits so that
+/// NaN values read from the same bytes are equal. Fields declared on the struct which aren't in the format
+/// are compared as usual.
+pub(super) fn generate_bitwise_eq(
+    struct_name: &syn::Ident,
+    items: &[Item],
+    meta: &Meta,
+    extra_fields: &[&syn::Ident],
+) -> Option<proc_macro2::TokenStream> {
+    if !meta.bitwise_eq {
+        return None;
+    }
+
+    let comparisons: Vec<_> = items
+        .iter()
+        .map(generate_field_comparison)
+        .chain(
+            extra_fields
+                .iter()
+                .map(|id| quote! { self.#id == other.#id }),
+        )
+        .collect();
+    let body = if comparisons.is_empty() {
+        quote! { true }
+    } else {
+        quote! { #(#comparisons)&&* }
+    };
+
+    Some(quote! {
+        impl ::std::cmp::PartialEq for #struct_name {
+            fn eq(&self, other: &Self) -> bool {
+                #body
+            }
+        }
+    })
+}
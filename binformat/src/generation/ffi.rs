@@ -0,0 +1,122 @@
+use super::{array_len, RUST_TYPES};
+use crate::{Encoding, Item};
+use proc_macro_error::abort;
+use quote::ToTokens;
+use std::collections::HashMap;
+
+/// Checks if a type has a stable C layout - scalars, booleans, arrays of these, and composite types
+/// from the format (which are also `#[repr(C)]`)
+fn is_ffi_safe(data_type: &syn::Type, types: &HashMap<syn::Ident, Vec<Item>>) -> bool {
+    match data_type {
+        syn::Type::Array(array) => array_len(array).is_some() && is_ffi_safe(&array.elem, types),
+        _ => {
+            let data_type = data_type.to_token_stream().to_string();
+            data_type == "bool"
+                || RUST_TYPES.contains(&data_type.as_str())
+                || types.keys().any(|name| *name == data_type)
+        }
+    }
+}
+
+/// Finds why an item can't be part of a `#[repr(C)]` struct, if it can't
+fn ffi_unsafe_reason(item: &Item, types: &HashMap<syn::Ident, Vec<Item>>) -> Option<&'static str> {
+    if item.repetition.is_some() {
+        Some("repeated fields are stored as `Vec`")
+    } else if item.condition.is_some() {
+        Some("conditional fields are stored as `Option`")
+    } else if matches!(item.encoding, Some(Encoding::TaggedUnion(_))) {
+        Some("tagged unions are enums holding data, which may not have a stable layout")
+    } else if item.encoding.is_some() {
+        Some("encoded fields are stored as `String`")
+    } else if item.enum_type.is_some() {
+        Some("enums may not have a stable layout")
+    } else if !is_ffi_safe(&item.data_type, types) {
+        Some("only numbers, booleans, arrays and types from the format have a stable layout")
+    } else {
+        None
+    }
+}
+
+/// Makes sure every item of a `#[repr(C)]` struct is FFI safe
+pub(super) fn validate_ffi_safe(
+    items: &[Item],
+    types: &HashMap<syn::Ident, Vec<Item>>,
+    struct_name: &syn::Ident,
+) {
+    for item in items {
+        if let Some(reason) = ffi_unsafe_reason(item, types) {
+            abort!(
+                struct_name,
+                "`{}` can't be used with repr_c: {}",
+                item.id,
+                reason
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_file;
+    use std::collections::BTreeMap;
+
+    fn parse(format: &str) -> crate::Format {
+        let file: BTreeMap<String, serde_yaml::Value> = serde_yaml::from_str(format).unwrap();
+        parse_file(file).unwrap()
+    }
+
+    #[test]
+    fn ffi_safe_items() {
+        let format = parse(
+            "
+types:
+  point:
+    - id: x
+      type: f32
+items:
+  - id: flag
+    type: bool
+  - id: values
+    type: '[u16; 4]'
+  - id: origin
+    type: point
+",
+        );
+
+        for item in &format.items {
+            assert_eq!(ffi_unsafe_reason(item, &format.types), None);
+        }
+    }
+
+    #[test]
+    fn ffi_unsafe_items() {
+        let format = parse(
+            "
+items:
+  - id: count
+    type: u8
+  - id: values
+    type: u8
+    repeat: Count(count)
+  - id: maybe
+    type: u8
+    if: 'count != 0'
+  - id: name
+    type: delimited_string
+    terminator: [0]
+  - id: pair
+    type: (u8, u8)
+  - id: external
+    type: Other
+",
+        );
+
+        let reasons: Vec<_> = format
+            .items
+            .iter()
+            .map(|item| ffi_unsafe_reason(item, &format.types).is_some())
+            .collect();
+        assert_eq!(reasons, [false, true, true, true, true, true]);
+    }
+}
@@ -0,0 +1,167 @@
+use super::{
+    encoded_size, positions::uses_position, reads::handle_simple_read, writes::handle_simple_write,
+    RUST_TYPES,
+};
+use crate::{Item, Meta, Repetition};
+use proc_macro_error::abort;
+use quote::{quote, ToTokens};
+
+/// Finds the item whose length is stored in the footer, along with the type of the length
+pub(super) fn footer_item(items: &[Item]) -> Option<(&Item, &syn::Type)> {
+    items.iter().find_map(|item| match &item.repetition {
+        Some(Repetition::Footer(length_type)) => Some((item, length_type)),
+        _ => None,
+    })
+}
+
+/// Makes sure a footer length is only used by a single plain field of the root struct, which is read
+/// from a seekable reader
+pub(super) fn validate_footers(
+    items: &[Item],
+    meta: &Meta,
+    struct_name: &syn::Ident,
+    is_root: bool,
+) {
+    let Some((item, length_type)) = footer_item(items) else {
+        return;
+    };
+    let id = &item.id;
+
+    if !is_root {
+        abort!(
+            struct_name,
+            "footer lengths can only be used in the root struct (on `{}`)",
+            id
+        )
+    }
+    let footers = items
+        .iter()
+        .filter(|item| matches!(item.repetition, Some(Repetition::Footer(_))))
+        .count();
+    if footers > 1 {
+        abort!(
+            struct_name,
+            "only one field can have its length in the footer"
+        )
+    }
+
+    let length_type = length_type.to_token_stream().to_string();
+    if !RUST_TYPES.contains(&length_type.as_str()) || length_type.starts_with('f') {
+        abort!(struct_name, "footer length of `{}` must be an integer", id)
+    }
+    if item.condition.is_some()
+        || item.big_endian_if.is_some()
+        || item.narrowing.is_some()
+        || item.layout.is_some()
+    {
+        abort!(struct_name, "footer lengths can't be combined with conditions, big_endian_if, narrow_type or layouts (on `{}`)", id)
+    }
+
+    // seeking happens on the reader given to `read`, so it can't be wrapped
+    if meta.checksum.is_some() || items.iter().any(uses_position) {
+        abort!(
+            struct_name,
+            "footer lengths can't be combined with crc, pad_to, align or assert_len (on `{}`)",
+            id
+        )
+    }
+}
+
+/// Generates a read of every element within the length stored in the footer, by seeking to the end of
+/// the reader for the length and back again
+pub(super) fn generate_footer_read(
+    length_type: &syn::Type,
+    statement: proc_macro2::TokenStream,
+    meta: &Meta,
+) -> proc_macro2::TokenStream {
+    let size = encoded_size(length_type).unwrap_or(0) as i64;
+    let length_read = handle_simple_read(length_type, meta.endianness, meta);
+
+    quote! {
+        (|| {
+            use ::std::io::Read as _;
+            use ::std::io::Seek as _;
+
+            let position = reader.stream_position()?;
+            reader.seek(::std::io::SeekFrom::End(-#size))?;
+            let length = (#length_read?) as u64;
+            reader.seek(::std::io::SeekFrom::Start(position))?;
+
+            let mut body = ::std::vec::Vec::new();
+            ::std::io::Read::take(&mut *reader, length).read_to_end(&mut body)?;
+            if (body.len() as u64) < length {
+                return Err(::std::io::ErrorKind::UnexpectedEof.into());
+            }
+
+            // elements are read from the body until it's used up
+            let reader = &mut body.as_slice();
+            let mut values = ::std::vec::Vec::new();
+            while !reader.is_empty() {
+                values.push(#statement?);
+            }
+            ::std::io::Result::Ok(values)
+        })()
+    }
+}
+
+/// Generates a write of every element, giving the number of bytes written so the length can be
+/// written in the footer
+pub(super) fn generate_footer_write(
+    id: &syn::Ident,
+    field: &syn::Ident,
+    statement: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        (|| {
+            use ::std::io::Write as _;
+            use ::byteorder::WriteBytesExt as _;
+
+            let mut body = ::std::vec::Vec::new();
+            {
+                let writer = &mut body;
+                for #id in self.#field.iter() {
+                    #statement?;
+                }
+            }
+            writer.write_all(&body)?;
+            ::std::io::Result::Ok(body.len())
+        })()
+    }
+}
+
+/// Generates the extra bound needed on readers when a length is stored in the footer
+pub(super) fn generate_seek_bound(items: &[Item]) -> Option<proc_macro2::TokenStream> {
+    footer_item(items)?;
+
+    Some(quote! { + ::std::io::Seek })
+}
+
+/// Generates a skip over the footer, so reading consumes the whole struct
+pub(super) fn generate_footer_skip(items: &[Item]) -> Option<proc_macro2::TokenStream> {
+    let (_, length_type) = footer_item(items)?;
+    let size = encoded_size(length_type)?;
+
+    Some(quote! {
+        reader.read_exact(&mut [0u8; #size])?;
+    })
+}
+
+/// Generates the write of the footer, from the length given by the footer item's write
+pub(super) fn generate_footer_length_write(
+    items: &[Item],
+    meta: &Meta,
+) -> Option<proc_macro2::TokenStream> {
+    let (item, length_type) = footer_item(items)?;
+    let id = &item.id;
+    let write = handle_simple_write(&quote! { length }, length_type, meta.endianness, meta);
+
+    Some(quote! {
+        let length = <#length_type>::try_from(_footer_length).map_err(|_| {
+            ::std::io::Error::new(
+                ::std::io::ErrorKind::InvalidInput,
+                concat!("`", stringify!(#id), "` is too long for its footer length"),
+            )
+        })?;
+        #write?;
+    })
+}
@@ -0,0 +1,113 @@
+use super::{
+    endianness_of, field_name, plain_type_fields, reads::handle_simple_read,
+    writes::handle_simple_write,
+};
+use crate::{Format, Item, Repetition};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+/// Finds the fields of the composite type stored column-wise by an item, making sure each field
+/// can be read on its own.
+fn column_fields<'a>(item: &Item, format: &'a Format, struct_name: &syn::Ident) -> &'a [Item] {
+    let id = &item.id;
+
+    if !matches!(item.repetition, Some(Repetition::Count(_))) {
+        abort!(
+            struct_name,
+            "layout `soa` needs a field repeated with `Count` (on `{}`)",
+            id
+        )
+    }
+    if item.condition.is_some()
+        || item.reference.is_some()
+        || item.big_endian_if.is_some()
+        || item.byteswap
+    {
+        abort!(
+            struct_name,
+            "layout `soa` can't be combined with other options (on `{}`)",
+            id
+        )
+    }
+
+    plain_type_fields(
+        item,
+        format,
+        "layout `soa`",
+        "stored column-wise",
+        struct_name,
+    )
+}
+
+/// Generates a read of a repeated composite stored column-wise, reading every column in turn before
+/// transposing them into elements
+pub(super) fn generate_soa_read(
+    item: &Item,
+    format: &Format,
+    struct_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let fields = column_fields(item, format, struct_name);
+    let Some(Repetition::Count(count)) = &item.repetition else {
+        unreachable!("checked by column_fields")
+    };
+    let data_type = &item.data_type;
+
+    let ids: Vec<_> = fields.iter().map(field_name).collect();
+    let columns: Vec<_> = ids
+        .iter()
+        .map(|id| format_ident!("column_{}", id))
+        .collect();
+    let reads = fields.iter().map(|field| {
+        let endianness = endianness_of(field, &format.meta);
+        handle_simple_read(&field.data_type, endianness, &format.meta)
+    });
+
+    quote! {
+        (|| {
+            let count = #count;
+            #(
+                let mut #columns = (0..count)
+                    .map(|_| #reads)
+                    .collect::<::std::io::Result<Vec<_>>>()?
+                    .into_iter();
+            )*
+
+            ::std::io::Result::Ok(
+                ::std::iter::from_fn(|| Some(#data_type { #(#ids: #columns.next()?),* }))
+                    .collect::<Vec<_>>()
+            )
+        })()
+    }
+}
+
+/// Generates a write of a repeated composite stored column-wise, writing each field of every element
+/// before moving on to the next field
+pub(super) fn generate_soa_write(
+    item: &Item,
+    format: &Format,
+    struct_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let fields = column_fields(item, format, struct_name);
+    let id = field_name(item);
+
+    let writes = fields.iter().map(|field| {
+        let field_id = field_name(field);
+        let endianness = endianness_of(field, &format.meta);
+        handle_simple_write(
+            &quote! { element.#field_id },
+            &field.data_type,
+            endianness,
+            &format.meta,
+        )
+    });
+
+    quote! {
+        (|| {
+            #(
+                self.#id.iter().try_for_each(|element| #writes)?;
+            )*
+
+            ::std::io::Result::Ok(())
+        })()
+    }
+}
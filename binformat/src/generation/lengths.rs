@@ -0,0 +1,67 @@
+use super::{field_name_of, RUST_TYPES};
+use crate::{Encoding, Item};
+use proc_macro_error::abort;
+use quote::{quote, ToTokens};
+
+/// Makes sure every length is a plain integer field giving the length of a repeated field or string later
+/// in the same struct, so the length can be worked out from `self` before anything is written.
+pub(super) fn validate_lengths(items: &[Item], struct_name: &syn::Ident) {
+    for (index, item) in items.iter().enumerate() {
+        let Some(target) = &item.length_of else {
+            continue;
+        };
+
+        let data_type = item.data_type.to_token_stream().to_string();
+        if !RUST_TYPES.contains(&data_type.as_str()) || data_type.starts_with('f') {
+            abort!(struct_name, "length `{}` must be an integer", item.id)
+        }
+        if item.condition.is_some()
+            || item.repetition.is_some()
+            || item.reference.is_some()
+            || item.narrowing.is_some()
+        {
+            abort!(struct_name, "length `{}` must be a plain field", item.id)
+        }
+
+        match items[index + 1..].iter().find(|other| &other.id == target) {
+            Some(other)
+                if other.repetition.is_some()
+                    || matches!(other.encoding, Some(Encoding::LengthPrefixedString(_))) => {}
+            Some(_) => abort!(
+                struct_name,
+                "`{}` must be a repeated field or string to have a length",
+                target
+            ),
+            None => abort!(
+                struct_name,
+                "`{}` must be defined after its length `{}`",
+                target,
+                item.id
+            ),
+        }
+    }
+}
+
+/// Wraps the write of a length so it writes the current length of its target (available to `write` as
+/// `length`), rather than the stored value.
+pub(super) fn generate_length_write(
+    item: &Item,
+    items: &[Item],
+    write: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let Some(target) = &item.length_of else {
+        return write;
+    };
+    let Item { id, data_type, .. } = item;
+    let field = field_name_of(items, target);
+
+    quote! {
+        match <#data_type>::try_from(self.#field.len()) {
+            Ok(length) => #write,
+            Err(_) => Err(::std::io::Error::new(
+                ::std::io::ErrorKind::InvalidInput,
+                concat!("`", stringify!(#target), "` is too long for its length `", stringify!(#id), "`"),
+            )),
+        }
+    }
+}
@@ -0,0 +1,33 @@
+use crate::Meta;
+use quote::quote;
+
+/// Number of bytes taken by the magic number leading the root struct
+pub(super) fn magic_size(meta: &Meta) -> usize {
+    meta.magic.as_ref().map_or(0, Vec::len)
+}
+
+/// Generates a read of the magic number before any fields, failing with `InvalidData` if it doesn't match
+pub(super) fn generate_magic_check(meta: &Meta) -> Option<proc_macro2::TokenStream> {
+    let magic = meta.magic.as_ref()?;
+    let size = magic.len();
+
+    Some(quote! {
+        let mut _magic = [0u8; #size];
+        ::std::io::Read::read_exact(reader, &mut _magic)?;
+        if _magic != [#(#magic),*] {
+            return Err(::std::io::Error::new(
+                ::std::io::ErrorKind::InvalidData,
+                format!("expected magic number {:?}, found {:?}", [#(#magic),*], _magic),
+            ));
+        }
+    })
+}
+
+/// Generates a write of the magic number before any fields
+pub(super) fn generate_magic_write(meta: &Meta) -> Option<proc_macro2::TokenStream> {
+    let magic = meta.magic.as_ref()?;
+
+    Some(quote! {
+        ::std::io::Write::write_all(writer, &[#(#magic),*])?;
+    })
+}
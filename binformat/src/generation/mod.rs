@@ -1,11 +1,49 @@
+mod accessors;
+mod aliases;
+mod arrays;
+mod bits;
+mod byte_orders;
+mod checksums;
+mod constants;
+mod conversions;
+mod declarations;
+mod defaults;
+mod encodings;
+mod equality;
+mod ffi;
+mod footers;
+mod layouts;
+mod lengths;
+mod magic;
+mod obfuscation;
+mod patterns;
+mod permutations;
+mod positions;
 mod reads;
+mod recovery;
+mod references;
+mod roundtrips;
+mod sizes;
 mod statements;
 mod structs;
+mod trailing;
+mod unions;
+mod widths;
 mod writes;
 
-use crate::Format;
-use quote::quote;
+use crate::{parse::Endianness, Format, Item, Meta};
+use accessors::generate_flattened_accessors;
+use aliases::resolve_aliases;
+use arrays::fix_literal_counts;
+use byte_orders::resolve_endian_of;
+use constants::substitute_constants;
+use conversions::generate_conversion;
+use proc_macro_error::abort;
+use quote::{quote, ToTokens};
+use roundtrips::generate_roundtrip_test;
+use std::path::{Path, PathBuf};
 use structs::generate_struct;
+use unions::generate_unions;
 
 #[derive(Clone, Copy)]
 enum Method {
@@ -14,21 +52,213 @@ enum Method {
 }
 
 const RUST_TYPES: &[&str] = &[
-    "u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64", "f32", "f64",
+    "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128", "f32", "f64",
 ];
 
+/// Checks if a type can be read and written - either a simple type (not something like `Option<T>`),
+/// a tuple of supported types, or an array of supported types with a literal length
+fn is_supported_type(data_type: &syn::Type) -> bool {
+    match data_type {
+        syn::Type::Path(syn::TypePath { path, .. }) => path
+            .segments
+            .first()
+            .map(|x| !x.ident.to_string().contains("<>"))
+            .unwrap_or(false),
+        syn::Type::Tuple(tuple) => tuple.elems.iter().all(is_supported_type),
+        syn::Type::Array(array) => is_supported_type(&array.elem) && array_len(array).is_some(),
+        _ => false,
+    }
+}
+
+/// Gives the name of the struct field generated for an item, which is its id unless it's renamed
+fn field_name(item: &Item) -> &syn::Ident {
+    item.rename.as_ref().unwrap_or(&item.id)
+}
+
+/// Finds the struct field name of the item with the given id, which expressions in the format refer to
+fn field_name_of<'a>(items: &'a [Item], id: &'a syn::Ident) -> &'a syn::Ident {
+    items
+        .iter()
+        .find(|item| &item.id == id)
+        .map_or(id, field_name)
+}
+
+/// Gives the byte order an item is stored in, which is the format's unless the item overrides it (and
+/// then swapped if the item is byteswapped)
+fn endianness_of(item: &Item, meta: &Meta) -> Endianness {
+    item.endianness
+        .unwrap_or(meta.endianness)
+        .swapped_if(item.byteswap)
+}
+
+/// Checks if a type is an integer, which can be compared against zero or widened and narrowed
+fn is_integer(data_type: &syn::Type) -> bool {
+    let data_type = data_type.to_token_stream().to_string();
+    RUST_TYPES.contains(&data_type.as_str()) && !data_type.starts_with('f')
+}
+
+/// Makes sure a field the item at `index` depends on is a plain integer read earlier in the same struct,
+/// so it's available as a local when reading and from `self` when writing. `key` names the option
+/// referring to the field, for errors.
+fn validate_prior_integer(
+    items: &[Item],
+    index: usize,
+    field: &syn::Ident,
+    key: &str,
+    struct_name: &syn::Ident,
+) {
+    match items[..index].iter().find(|other| &other.id == field) {
+        Some(other)
+            if is_integer(&other.data_type)
+                && other.condition.is_none()
+                && other.repetition.is_none() => {}
+        Some(_) => abort!(struct_name, "{} `{}` must be a plain integer", key, field),
+        None => abort!(
+            struct_name,
+            "{} `{}` must be defined before `{}`",
+            key,
+            field,
+            items[index].id
+        ),
+    }
+}
+
+/// Finds the fields of the composite type of an item, making sure each field is plain so it can be
+/// read on its own. `option` names the option needing this and `purpose` what it does to the fields,
+/// for errors.
+fn plain_type_fields<'a>(
+    item: &Item,
+    format: &'a Format,
+    option: &str,
+    purpose: &str,
+    struct_name: &syn::Ident,
+) -> &'a [Item] {
+    let type_name = item.data_type.to_token_stream().to_string();
+    let Some((_, fields)) = format
+        .types
+        .iter()
+        .find(|(name, _)| name.to_string() == type_name)
+    else {
+        abort!(
+            struct_name,
+            "{} needs a type defined in the format (on `{}`)",
+            option,
+            item.id
+        )
+    };
+
+    for field in fields {
+        if field.condition.is_some()
+            || field.repetition.is_some()
+            || field.encoding.is_some()
+            || field.reference.is_some()
+            || field.cipher_seed.is_some()
+            || field.big_endian_if.is_some()
+        {
+            abort!(
+                struct_name,
+                "fields of `{}` must be plain to be {} (on `{}`)",
+                type_name,
+                purpose,
+                field.id
+            )
+        }
+    }
+
+    fields
+}
+
+/// Finds the length of an array type, if given as a literal
+fn array_len(array: &syn::TypeArray) -> Option<usize> {
+    match &array.len {
+        syn::Expr::Lit(syn::ExprLit {
+            lit: syn::Lit::Int(len),
+            ..
+        }) => len.base10_parse().ok(),
+        _ => None,
+    }
+}
+
+/// Finds the number of bytes a type takes up when encoded, if it's always the same.
+///
+/// This differs from `size_of` for tuples, which are encoded without padding.
+fn encoded_size(data_type: &syn::Type) -> Option<usize> {
+    match data_type {
+        syn::Type::Tuple(tuple) => tuple.elems.iter().map(encoded_size).sum(),
+        syn::Type::Array(array) => Some(encoded_size(&array.elem)? * array_len(array)?),
+        _ => match &*data_type.to_token_stream().to_string() {
+            "u8" | "i8" | "bool" | "char" => Some(1),
+            "u16" | "i16" => Some(2),
+            "u32" | "i32" | "f32" => Some(4),
+            "u64" | "i64" | "f64" => Some(8),
+            "u128" | "i128" => Some(16),
+            _ => None,
+        },
+    }
+}
+
+/// Checks if a type is a scalar that's read with a single reader call (including booleans and characters)
+fn is_scalar(data_type: &syn::Type) -> bool {
+    let data_type = data_type.to_token_stream().to_string();
+    data_type == "bool" || data_type == "char" || RUST_TYPES.contains(&data_type.as_str())
+}
+
+/// Generates an `include_bytes!` of every format file read, so cargo rebuilds when one changes.
+/// The bytes themselves go unused.
+fn generate_tracking<'a>(paths: impl Iterator<Item = &'a Path>) -> proc_macro2::TokenStream {
+    let paths = paths.map(|path| {
+        // include_bytes! is relative to the source file, so the path has to be absolute
+        let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        path.to_string_lossy().into_owned()
+    });
+
+    quote! {
+        #(const _: &[u8] = include_bytes!(#paths);)*
+    }
+}
+
 /// Generate the entire chunk of code to be inserted
-pub(super) fn generate(item: syn::ItemStruct, format: Format) -> proc_macro::TokenStream {
+pub(super) fn generate(
+    item: syn::ItemStruct,
+    mut format: Format,
+    path: &Path,
+) -> proc_macro::TokenStream {
+    substitute_constants(&mut format);
+    resolve_aliases(&mut format);
+    resolve_endian_of(&mut format, &item.ident);
+    if format.meta.fixed_counts {
+        fix_literal_counts(&mut format);
+    }
+
     let types = format
         .types
         .iter()
-        .map(|items| generate_struct(&item, items.0, format.endianness, items.1));
+        .map(|items| generate_struct(&item, items.0, &format, items.1));
+
+    let main = generate_struct(&item, &item.ident, &format, &format.items);
+    let unions = generate_unions(&item, &format);
+    let conversion = format
+        .meta
+        .from
+        .as_ref()
+        .map(|from| generate_conversion(&item, &format.items, from));
 
-    let main = generate_struct(&item, &item.ident, format.endianness, &format.items);
+    let accessors = generate_flattened_accessors(&item.ident, &format);
+    let roundtrip_test = generate_roundtrip_test(&item.ident, &format.meta);
+    let tracking = generate_tracking(
+        std::iter::once(path)
+            .chain(format.includes.iter().map(PathBuf::as_path))
+            .chain(format.meta.from.iter().map(|from| from.path.as_path())),
+    );
 
     quote! {
         #(#types)*
         #main
+        #unions
+        #conversion
+        #accessors
+        #roundtrip_test
+        #tracking
     }
     .into()
 }
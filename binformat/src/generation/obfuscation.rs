@@ -0,0 +1,182 @@
+use super::{
+    encoded_size, endianness_of, field_name, field_name_of, is_integer, reads::handle_simple_read,
+    writes::handle_simple_write,
+};
+use crate::{parse::Endianness, Format, Item, Meta};
+use proc_macro_error::abort;
+use quote::{quote, ToTokens};
+
+/// Makes sure every composite enciphered with a `cipher_seed` is read as a whole, and its seed is a plain
+/// integer read earlier in the same struct - so the seed's stored bytes can be used as the key.
+pub(super) fn validate_ciphers(items: &[Item], struct_name: &syn::Ident) {
+    for (index, item) in items.iter().enumerate() {
+        let Some(seed) = &item.cipher_seed else {
+            continue;
+        };
+
+        if item.condition.is_some()
+            || item.repetition.is_some()
+            || item.layout.is_some()
+            || item.permute_seed.is_some()
+        {
+            abort!(
+                struct_name,
+                "cipher_seed can't be combined with conditions, repeats, layouts or permute_seed (on `{}`)",
+                item.id
+            )
+        }
+
+        match items[..index].iter().find(|other| &other.id == seed) {
+            Some(other)
+                if is_integer(&other.data_type)
+                    && other.condition.is_none()
+                    && other.repetition.is_none()
+                    && other.encoding.is_none()
+                    && other.big_endian_if.is_none()
+                    && other.narrowing.is_none() => {}
+            Some(_) => abort!(struct_name, "seed `{}` must be a plain integer", seed),
+            None => abort!(
+                struct_name,
+                "seed `{}` must be defined before `{}`",
+                seed,
+                item.id
+            ),
+        }
+    }
+}
+
+/// Works out how many bytes the composite type enciphered by an item takes up, making sure every field
+/// has a fixed size so the bytes can be read before they're parsed.
+fn enciphered_size(item: &Item, format: &Format, struct_name: &syn::Ident) -> usize {
+    let type_name = item.data_type.to_token_stream().to_string();
+    let Some((_, fields)) = format
+        .types
+        .iter()
+        .find(|(name, _)| name.to_string() == type_name)
+    else {
+        abort!(
+            struct_name,
+            "cipher_seed needs a type defined in the format (on `{}`)",
+            item.id
+        )
+    };
+
+    fields
+        .iter()
+        .map(|field| match encoded_size(&field.data_type) {
+            Some(size)
+                if field.condition.is_none()
+                    && field.repetition.is_none()
+                    && field.encoding.is_none()
+                    && field.narrowing.is_none()
+                    && field.align.is_none()
+                    && field.pad_to.is_none()
+                    && field.skip_before.is_none()
+                    && field.skip_after.is_none() =>
+            {
+                size
+            }
+            _ => abort!(
+                struct_name,
+                "fields of `{}` must be plain and have a fixed size to be enciphered (on `{}`)",
+                type_name,
+                field.id
+            ),
+        })
+        .sum()
+}
+
+/// Generates the cipher shared by reads and writes, xoring `bytes` with the seed's stored bytes repeated
+fn generate_cipher(
+    seed: proc_macro2::TokenStream,
+    seed_item: &Item,
+    meta: &Meta,
+) -> proc_macro2::TokenStream {
+    let key = match endianness_of(seed_item, meta) {
+        Endianness::Little => quote! { #seed.to_le_bytes() },
+        Endianness::Big => quote! { #seed.to_be_bytes() },
+    };
+
+    quote! {
+        let key = #key;
+        bytes
+            .iter_mut()
+            .zip(key.iter().cycle())
+            .for_each(|(byte, key)| *byte ^= key);
+    }
+}
+
+/// Finds the item an enciphered item is seeded by
+fn seed_item<'a>(item: &Item, items: &'a [Item]) -> &'a Item {
+    let Some(seed) = &item.cipher_seed else {
+        unreachable!("only called for enciphered items")
+    };
+
+    items
+        .iter()
+        .find(|other| &other.id == seed)
+        .expect("seeds are validated to exist")
+}
+
+/// Generates a read of an enciphered composite in two steps - reading and deciphering all of its bytes,
+/// and then parsing it from them
+pub(super) fn generate_deciphered_read(
+    item: &Item,
+    items: &[Item],
+    format: &Format,
+    struct_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let size = enciphered_size(item, format, struct_name);
+    let seed_item = seed_item(item, items);
+    let seed = &seed_item.id;
+    let cipher = generate_cipher(quote! { #seed }, seed_item, &format.meta);
+    let read = handle_simple_read(
+        &item.data_type,
+        endianness_of(item, &format.meta),
+        &format.meta,
+    );
+
+    quote! {
+        (|| {
+            let mut bytes = [0; #size];
+            ::std::io::Read::read_exact(reader, &mut bytes)?;
+            #cipher
+
+            let reader = &mut &bytes[..];
+            #read
+        })()
+    }
+}
+
+/// Generates a write of an enciphered composite, serializing it before enciphering and emitting its bytes
+pub(super) fn generate_enciphered_write(
+    item: &Item,
+    items: &[Item],
+    format: &Format,
+    struct_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let size = enciphered_size(item, format, struct_name);
+    let seed_item = seed_item(item, items);
+    let seed = field_name_of(items, &seed_item.id);
+    let cipher = generate_cipher(quote! { self.#seed }, seed_item, &format.meta);
+    let id = field_name(item);
+    let write = handle_simple_write(
+        &quote! { self.#id },
+        &item.data_type,
+        endianness_of(item, &format.meta),
+        &format.meta,
+    );
+
+    quote! {
+        (|| {
+            let mut bytes = ::std::vec::Vec::with_capacity(#size);
+            {
+                let writer = &mut bytes;
+                #write?;
+            }
+            #cipher
+
+            ::std::io::Write::write_all(writer, &bytes)
+        })()
+    }
+}
@@ -0,0 +1,60 @@
+use crate::{Item, Pattern, Predicate};
+use proc_macro_error::abort;
+use quote::{quote, ToTokens};
+
+/// Makes sure fields matched against a pattern in the same struct are plain strings read before the
+/// item matching them. Paths (like `_root.name`) are left to the compiler.
+pub(super) fn validate_matches(items: &[Item], struct_name: &syn::Ident) {
+    for (index, item) in items.iter().enumerate() {
+        let Some(Predicate::Match { field, .. }) = item
+            .condition
+            .as_ref()
+            .map(|condition| &condition.predicate)
+        else {
+            continue;
+        };
+        let syn::Expr::Path(path) = &**field else {
+            continue;
+        };
+        let Some(field) = path.path.get_ident() else {
+            continue;
+        };
+
+        match items[..index].iter().find(|other| &other.id == field) {
+            Some(other)
+                if other.data_type.to_token_stream().to_string() == "String"
+                    && other.condition.is_none()
+                    && other.repetition.is_none() => {}
+            Some(_) => abort!(
+                struct_name,
+                "`{}` must be a plain string field to be matched (on `{}`)",
+                field,
+                item.id
+            ),
+            None => abort!(
+                struct_name,
+                "`{}` must be defined before `{}` to be matched",
+                field,
+                item.id
+            ),
+        }
+    }
+}
+
+/// Generates the expression deciding whether a conditional item is present
+pub(super) fn generate_predicate(predicate: &Predicate) -> proc_macro2::TokenStream {
+    match predicate {
+        Predicate::Expression(expression) => expression.to_token_stream(),
+        Predicate::Match {
+            field,
+            pattern: Pattern::StartsWith(prefix),
+        } => quote! { #field.starts_with(#prefix) },
+        Predicate::Match {
+            field,
+            pattern: Pattern::Contains(substring),
+        } => quote! { #field.contains(#substring) },
+        Predicate::DataRemaining => {
+            unreachable!("reads of optional_eof fields check for the end of the data themselves")
+        }
+    }
+}
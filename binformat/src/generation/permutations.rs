@@ -0,0 +1,139 @@
+use super::{
+    endianness_of, field_name, field_name_of, plain_type_fields, reads::handle_simple_read,
+    writes::handle_simple_write,
+};
+use crate::{Format, Item};
+use proc_macro_error::abort;
+use quote::{format_ident, quote};
+
+/// Makes sure every permutation seed is a plain field read earlier in the same struct, so it's
+/// available as a local when reading and as `self.seed` when writing.
+pub(super) fn validate_permutations(items: &[Item], struct_name: &syn::Ident) {
+    for (index, item) in items.iter().enumerate() {
+        let Some(seed) = &item.permute_seed else {
+            continue;
+        };
+
+        if item.condition.is_some() || item.repetition.is_some() || item.layout.is_some() {
+            abort!(
+                struct_name,
+                "permute_seed can't be combined with conditions, repeats or layouts (on `{}`)",
+                item.id
+            )
+        }
+
+        match items[..index].iter().find(|other| &other.id == seed) {
+            Some(other) if other.condition.is_none() && other.repetition.is_none() => {}
+            Some(_) => abort!(struct_name, "seed `{}` must be a plain field", seed),
+            None => abort!(
+                struct_name,
+                "seed `{}` must be defined before `{}`",
+                seed,
+                item.id
+            ),
+        }
+    }
+}
+
+/// Finds the fields of the composite type whose order is permuted by an item, making sure each field
+/// can be read on its own.
+fn permuted_fields<'a>(item: &Item, format: &'a Format, struct_name: &syn::Ident) -> &'a [Item] {
+    plain_type_fields(item, format, "permute_seed", "permuted", struct_name)
+}
+
+/// Generates the order fields are stored in, shuffled with a keystream seeded by `seed`
+fn generate_order(seed: proc_macro2::TokenStream, len: usize) -> proc_macro2::TokenStream {
+    quote! {
+        {
+            let mut order: [usize; #len] = ::std::array::from_fn(|index| index);
+            let mut state = #seed as u64;
+            for index in (1..#len).rev() {
+                state = state
+                    .wrapping_mul(6364136223846793005)
+                    .wrapping_add(1442695040888963407);
+                order.swap(index, (state >> 33) as usize % (index + 1));
+            }
+            order
+        }
+    }
+}
+
+/// Generates a read of a composite whose fields are stored in a seeded order, reading each field as
+/// it's reached before putting them back together
+pub(super) fn generate_permuted_read(
+    item: &Item,
+    format: &Format,
+    struct_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let fields = permuted_fields(item, format, struct_name);
+    let Some(seed) = &item.permute_seed else {
+        unreachable!("only called for permuted items")
+    };
+    let data_type = &item.data_type;
+
+    let order = generate_order(quote! { #seed }, fields.len());
+    let ids: Vec<_> = fields.iter().map(field_name).collect();
+    let values: Vec<_> = ids.iter().map(|id| format_ident!("field_{}", id)).collect();
+    let indices = 0..fields.len();
+    let reads = fields.iter().map(|field| {
+        let endianness = endianness_of(field, &format.meta);
+        handle_simple_read(&field.data_type, endianness, &format.meta)
+    });
+
+    quote! {
+        (|| {
+            #(let mut #values = None;)*
+            for index in #order {
+                match index {
+                    #(#indices => #values = Some(#reads?),)*
+                    _ => unreachable!("permutations only hold field indices"),
+                }
+            }
+
+            ::std::io::Result::Ok(#data_type {
+                #(#ids: #values.expect("permutations hold every field index")),*
+            })
+        })()
+    }
+}
+
+/// Generates a write of a composite whose fields are stored in a seeded order
+pub(super) fn generate_permuted_write(
+    item: &Item,
+    items: &[Item],
+    format: &Format,
+    struct_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let fields = permuted_fields(item, format, struct_name);
+    let Some(seed) = &item.permute_seed else {
+        unreachable!("only called for permuted items")
+    };
+    let id = field_name(item);
+    let seed = field_name_of(items, seed);
+
+    let order = generate_order(quote! { self.#seed }, fields.len());
+    let indices = 0..fields.len();
+    let writes = fields.iter().map(|field| {
+        let field_id = field_name(field);
+        let endianness = endianness_of(field, &format.meta);
+        handle_simple_write(
+            &quote! { self.#id.#field_id },
+            &field.data_type,
+            endianness,
+            &format.meta,
+        )
+    });
+
+    quote! {
+        (|| {
+            for index in #order {
+                match index {
+                    #(#indices => #writes?,)*
+                    _ => unreachable!("permutations only hold field indices"),
+                }
+            }
+
+            ::std::io::Result::Ok(())
+        })()
+    }
+}
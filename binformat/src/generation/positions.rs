@@ -0,0 +1,210 @@
+use crate::Item;
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Checks if an item depends on the position it's read or written at, needing the position to be tracked
+pub(super) fn uses_position(item: &Item) -> bool {
+    item.pad_to.is_some() || item.align.is_some() || item.assert_len.is_some()
+}
+
+/// Makes sure lengths are only asserted on items which are always present
+pub(super) fn validate_positions(items: &[Item], struct_name: &syn::Ident) {
+    if let Some(item) = items
+        .iter()
+        .find(|item| item.assert_len.is_some() && item.condition.is_some())
+    {
+        abort!(
+            struct_name,
+            "assert_len can't be used on conditional items (on `{}`)",
+            item.id
+        )
+    }
+}
+
+/// Generates code wrapping the reader so the number of bytes read by the struct is tracked in
+/// `_position`, if any of its items depend on it or `track` is set. This works for any reader, without
+/// needing `Seek` - but seekable readers stay seekable, with seeks moving the position too.
+pub(super) fn generate_positioned_reader(
+    items: &[Item],
+    track: bool,
+) -> Option<proc_macro2::TokenStream> {
+    if !track {
+        items.iter().find(|item| uses_position(item))?;
+    }
+
+    Some(quote! {
+        use ::std::io::Read as _;
+        use ::byteorder::ReadBytesExt as _;
+
+        struct PositionedReader<'a, R> {
+            inner: &'a mut R,
+            position: &'a ::std::cell::Cell<usize>,
+        }
+
+        impl<R: ::std::io::Read> ::std::io::Read for PositionedReader<'_, R> {
+            fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+                let read = self.inner.read(buf)?;
+                self.position.set(self.position.get() + read);
+                Ok(read)
+            }
+        }
+
+        impl<R: ::std::io::Seek> ::std::io::Seek for PositionedReader<'_, R> {
+            fn seek(&mut self, pos: ::std::io::SeekFrom) -> ::std::io::Result<u64> {
+                let before = self.inner.stream_position()?;
+                let after = self.inner.seek(pos)?;
+                self.position
+                    .set(self.position.get().wrapping_add(after.wrapping_sub(before) as usize));
+                Ok(after)
+            }
+        }
+
+        let _position = ::std::cell::Cell::new(0usize);
+        let reader = &mut PositionedReader {
+            inner: &mut *reader,
+            position: &_position,
+        };
+    })
+}
+
+/// Generates code wrapping the writer so the number of bytes written by the struct is tracked in
+/// `_position`, if any of its items depend on it
+pub(super) fn generate_positioned_writer(items: &[Item]) -> Option<proc_macro2::TokenStream> {
+    items.iter().find(|item| uses_position(item))?;
+
+    Some(quote! {
+        use ::std::io::Write as _;
+        use ::byteorder::WriteBytesExt as _;
+
+        struct PositionedWriter<'a, W> {
+            inner: &'a mut W,
+            position: &'a ::std::cell::Cell<usize>,
+        }
+
+        impl<W: ::std::io::Write> ::std::io::Write for PositionedWriter<'_, W> {
+            fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+                let written = self.inner.write(buf)?;
+                self.position.set(self.position.get() + written);
+                Ok(written)
+            }
+
+            fn flush(&mut self) -> ::std::io::Result<()> {
+                self.inner.flush()
+            }
+        }
+
+        let _position = ::std::cell::Cell::new(0usize);
+        let writer = &mut PositionedWriter {
+            inner: &mut *writer,
+            position: &_position,
+        };
+    })
+}
+
+/// Wraps the read of an item with everything depending on its position - skipping to its alignment
+/// beforehand, checking how many bytes it took, then skipping its padding. Reserved space around the item
+/// is skipped first and last.
+pub(super) fn generate_positioned_read(
+    item: &Item,
+    read: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let align = item.align.map(|align| {
+        quote! {
+            let padding = (#align - _position.get() % #align) % #align;
+            reader.read_exact(&mut [0u8; #align][..padding])?
+        }
+    });
+    let (start, check) = generate_length_check(item, quote! { InvalidData });
+    let padding = item.pad_to.map(|pad_to| {
+        quote! {
+            let padding = (#pad_to - _position.get() % #pad_to) % #pad_to;
+            reader.read_exact(&mut [0u8; #pad_to][..padding])?
+        }
+    });
+    let [skip_before, skip_after] = [item.skip_before, item.skip_after].map(|skip| {
+        skip.map(|skip| quote! { ::std::io::Read::read_exact(reader, &mut [0u8; #skip])? })
+    });
+
+    // statements are joined without a trailing semicolon, like any other read
+    let statements = [
+        skip_before,
+        align,
+        start,
+        Some(read),
+        check,
+        padding,
+        skip_after,
+    ]
+    .into_iter()
+    .flatten();
+    quote! { #(#statements);* }
+}
+
+/// Wraps the write of an item with everything depending on its position - zero filling up to its
+/// alignment beforehand, checking how many bytes it took, then zero filling its padding. Reserved space
+/// around the item is zero filled first and last.
+pub(super) fn generate_positioned_write(
+    item: &Item,
+    write: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let align = item.align.map(|align| {
+        quote! {
+            let padding = (#align - _position.get() % #align) % #align;
+            writer.write_all(&[0u8; #align][..padding])?
+        }
+    });
+    let (start, check) = generate_length_check(item, quote! { InvalidInput });
+    let padding = item.pad_to.map(|pad_to| {
+        quote! {
+            let padding = (#pad_to - _position.get() % #pad_to) % #pad_to;
+            writer.write_all(&[0u8; #pad_to][..padding])?
+        }
+    });
+    let [skip_before, skip_after] = [item.skip_before, item.skip_after].map(|skip| {
+        skip.map(|skip| quote! { ::std::io::Write::write_all(writer, &[0u8; #skip])? })
+    });
+
+    let statements = [
+        skip_before,
+        align,
+        start,
+        Some(write),
+        check,
+        padding,
+        skip_after,
+    ]
+    .into_iter()
+    .flatten();
+    quote! { #(#statements);* }
+}
+
+/// Generates the record of where an item starts, and the check it took exactly its `assert_len` bytes
+fn generate_length_check(
+    item: &Item,
+    kind: proc_macro2::TokenStream,
+) -> (
+    Option<proc_macro2::TokenStream>,
+    Option<proc_macro2::TokenStream>,
+) {
+    let Some(len) = item.assert_len else {
+        return (None, None);
+    };
+    let id = &item.id;
+
+    let start = quote! { let _start = _position.get() };
+    let check = quote! {
+        if _position.get() - _start != #len {
+            return Err(::std::io::Error::new(
+                ::std::io::ErrorKind::#kind,
+                format!(
+                    "`{}` took {} bytes rather than {}",
+                    stringify!(#id),
+                    _position.get() - _start,
+                    #len,
+                ),
+            ));
+        }
+    };
+
+    (Some(start), Some(check))
+}
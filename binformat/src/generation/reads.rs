@@ -1,24 +1,93 @@
-use super::RUST_TYPES;
+use super::{encoded_size, endianness_of, is_supported_type, RUST_TYPES};
 use crate::{
-    generation::{statements::create_statement, Method},
+    generation::{
+        bits::generate_bits_read,
+        checksums::generate_checksum_field_read,
+        defaults::generate_default_value,
+        encodings::generate_encoded_read,
+        layouts::generate_soa_read,
+        obfuscation::generate_deciphered_read,
+        patterns::generate_predicate,
+        permutations::generate_permuted_read,
+        positions::generate_positioned_read,
+        references::{generate_enum_read, generate_reference_check},
+        statements::create_statement,
+        trailing::generate_optional_eof_read,
+        widths::generate_narrowed_read,
+        Method,
+    },
     parse::Endianness,
-    Condition, Item,
+    Condition, Encoding, Format, Item, Meta, Predicate,
 };
 use proc_macro_error::abort;
 use quote::{format_ident, quote, ToTokens};
-use syn::{Type, TypePath};
 
-/// Creates simple read code for the following 3 cases:
+/// Creates simple read code for the following 5 cases:
 ///     - Simple rust types like u16 where can just call reader function with correct endianness
-///     - Booleans where need to do a simple conversion
+///     - Booleans and characters where need to do a simple conversion
+///     - Tuples where each element is read in order
+///     - Arrays where each element is read in place, without allocating
 ///     - Composite types where we simply call the correct function
-fn handle_simple_read(data_type: &syn::Type, endianness: Endianness) -> proc_macro2::TokenStream {
+pub(super) fn handle_simple_read(
+    data_type: &syn::Type,
+    endianness: Endianness,
+    meta: &Meta,
+) -> proc_macro2::TokenStream {
     // need to check if type is existing rust type or custom
-    if RUST_TYPES.contains(&&*data_type.to_token_stream().to_string()) {
+    if let syn::Type::Tuple(tuple) = data_type {
+        // read each element in turn, stopping at the first error
+        let reads = tuple
+            .elems
+            .iter()
+            .map(|elem| handle_simple_read(elem, endianness, meta));
+
+        quote! { (|| ::std::io::Result::Ok((#(#reads?,)*)))() }
+    } else if let syn::Type::Array(array) = data_type {
+        let syn::TypeArray { elem, len, .. } = array;
+        let read = handle_simple_read(elem, endianness, meta);
+
+        // elements without a fixed size (like composite types) aren't `Copy`, so can't be written over a
+        // filled array - instead build the array element by element, stopping reads at the first error
+        if encoded_size(elem).is_none() {
+            return quote! {
+                (|| {
+                    let mut error = None;
+                    let array: [Option<#elem>; #len] = ::std::array::from_fn(|_| {
+                        if error.is_some() {
+                            return None;
+                        }
+                        #read.map_err(|e| error = Some(e)).ok()
+                    });
+
+                    match error {
+                        Some(error) => Err(error),
+                        None => ::std::io::Result::Ok(array.map(|element| element.unwrap())),
+                    }
+                })()
+            };
+        }
+
+        // otherwise fill the array in place
+        let default = generate_default_value(elem);
+        quote! {
+            (|| {
+                let mut array = [#default; #len];
+                for element in array.iter_mut() {
+                    *element = #read?;
+                }
+                ::std::io::Result::Ok(array)
+            })()
+        }
+    } else if RUST_TYPES.contains(&&*data_type.to_token_stream().to_string()) {
         // simple case where reader code exists, can just reader::read_<type>();
 
         let fn_call = format_ident!("read_{}", data_type.to_token_stream().to_string());
 
+        // single bytes have no endianness
+        if matches!(&*data_type.to_token_stream().to_string(), "u8" | "i8") {
+            return quote! { reader.#fn_call() };
+        }
+
         match endianness {
             Endianness::Little => {
                 quote! {  reader.#fn_call::<::byteorder::LittleEndian>() }
@@ -31,12 +100,21 @@ fn handle_simple_read(data_type: &syn::Type, endianness: Endianness) -> proc_mac
         // matches boolean logic in original savecodec2
 
         quote! { reader.read_u8().map(|i| i != 0) }
+    } else if data_type.to_token_stream().to_string() == "char" {
+        // characters are a single byte, so any byte is a valid character
+        quote! { reader.read_u8().map(char::from) }
     } else {
         // more complex case where needs to use custom implementation
         // pass root context for conditional support
         // e.g. <type>::read(&reader, &_root);
 
-        quote! { #data_type::read(reader, &_root) }
+        let read_fn = &meta.read_fn;
+        if meta.max_depth.is_some() {
+            // composite types are one level deeper than the struct reading them
+            quote! { #data_type::#read_fn(reader, &_root, _depth + 1) }
+        } else {
+            quote! { #data_type::#read_fn(reader, &_root) }
+        }
     }
 }
 
@@ -46,10 +124,17 @@ pub(super) fn generate_conditional_read(
     statement: proc_macro2::TokenStream,
     data_type: &syn::Type,
 ) -> proc_macro2::TokenStream {
-    // make sure to advance pointer if needed
+    if matches!(condition.predicate, Predicate::DataRemaining) {
+        return generate_optional_eof_read(statement);
+    }
+
+    // make sure to advance pointer if needed, by exactly as many bytes as the value would take
     let else_body = if condition.advance_if_false {
+        let size = encoded_size(data_type).unwrap_or_else(|| {
+            abort!(data_type, "advance_if_false needs a type with a fixed size")
+        });
         quote! {
-            reader.read_exact(&mut [0u8; std::mem::size_of::<#data_type>()])?;
+            reader.read_exact(&mut [0u8; #size])?;
             None
         }
     } else {
@@ -58,7 +143,7 @@ pub(super) fn generate_conditional_read(
         }
     };
 
-    let expr = &condition.expression;
+    let expr = generate_predicate(&condition.predicate);
     quote! {
         if #expr {
             Some(#statement?)
@@ -68,20 +153,22 @@ pub(super) fn generate_conditional_read(
     }
 }
 
+/// Checks if a type is a number or tuple or array of numbers, whose bytes can be swapped
+pub(super) fn is_swappable(data_type: &syn::Type) -> bool {
+    match data_type {
+        syn::Type::Tuple(tuple) => tuple.elems.iter().all(is_swappable),
+        syn::Type::Array(array) => is_swappable(&array.elem),
+        _ => RUST_TYPES.contains(&&*data_type.to_token_stream().to_string()),
+    }
+}
+
 /// Generates a vector of variable assignments that read the correct type from a reader.
 pub(super) fn generate_read_calls(
     items: &[Item],
-    endianness: Endianness,
+    format: &Format,
     struct_name: &syn::Ident,
 ) -> Vec<proc_macro2::TokenStream> {
-    /// Checks if type contains any symbols which indicate if it's a complex type (like `Option<T>`)
-    #[inline(always)]
-    fn is_simple_type(path: &syn::Path) -> bool {
-        path.segments
-            .first()
-            .map(|x| !x.ident.to_string().contains("<>"))
-            .unwrap_or(false)
-    }
+    let meta = &format.meta;
 
     items
         .iter()
@@ -90,22 +177,87 @@ pub(super) fn generate_read_calls(
                 id,
                 data_type,
                 condition,
-                repetition,
+                ..
             } = item;
 
-            if let Type::Path(TypePath { path, .. }) = data_type && is_simple_type(path) {
-                let read = handle_simple_read(data_type, endianness);
-                let read = create_statement(read, id, data_type, condition, repetition, Method::Reading);
+            // column-wise layouts are read as a whole
+            if item.layout.is_some() {
+                let read = generate_soa_read(item, format, struct_name);
+                return quote! { let #id = #read? };
+            }
+
+            // bit fields are read from the unit they share
+            if matches!(item.encoding, Some(Encoding::Bits(_))) {
+                return generate_bits_read(item, items, meta);
+            }
+
+            // checksum fields are checked against the bytes in their range
+            if matches!(item.encoding, Some(Encoding::Checksum(_))) {
+                let read = generate_checksum_field_read(item, meta);
+                return quote! { let #id = #read? };
+            }
+
+            // permuted fields are read as a whole, in their stored order
+            if item.permute_seed.is_some() {
+                let read = generate_permuted_read(item, format, struct_name);
+                return quote! { let #id = #read? };
+            }
+
+            // enciphered composites are read as a whole, and deciphered before they're parsed
+            if item.cipher_seed.is_some() {
+                let read = generate_deciphered_read(item, items, format, struct_name);
+                return quote! { let #id = #read? };
+            }
+
+            // swapping is done through endianness, so only works for types which directly use it
+            if item.byteswap && !is_swappable(data_type) {
+                abort!(struct_name, "byteswap can only be used on numbers, and tuples or arrays of numbers (on `{}`)", id)
+            }
+
+            if is_supported_type(data_type) {
+                let read = match (&item.encoding, &item.big_endian_if) {
+                    (Some(encoding), _) => generate_encoded_read(encoding, meta),
+                    (None, Some(expr)) => {
+                        // choose the endianness at runtime
+                        let big = Endianness::Big.swapped_if(item.byteswap);
+                        let big = handle_simple_read(data_type, big, meta);
+                        let little = Endianness::Little.swapped_if(item.byteswap);
+                        let little = handle_simple_read(data_type, little, meta);
+                        quote! { if #expr { #big } else { #little } }
+                    }
+                    (None, None) => {
+                        let endianness = endianness_of(item, meta);
+                        let read = handle_simple_read(data_type, endianness, meta);
+                        match &item.narrowing {
+                            Some(narrowing) => {
+                                let narrow_read =
+                                    handle_simple_read(&narrowing.data_type, endianness, meta);
+                                generate_narrowed_read(narrowing, data_type, narrow_read, read)
+                            }
+                            None => read,
+                        }
+                    }
+                };
+                let read = generate_enum_read(item, read);
+                let read = create_statement(read, item, meta, Method::Reading);
 
                 // conditional code has custom error handling, otherwise just standard error propagation
-                if condition.is_some() {
+                let read = if condition.is_some() && item.repetition.is_none() {
                     quote! { let #id = #read }
                 } else {
                     quote! { let #id = #read? }
+                };
+
+                // references also need to be checked against their table
+                match generate_reference_check(item, items) {
+                    Some(check) => quote! { #read; #check },
+                    None => read,
                 }
             } else {
                 abort!(struct_name, "can only handle simple types (try removing any Options or Results in config file)")
             }
         })
+        .zip(items)
+        .map(|(read, item)| generate_positioned_read(item, read))
         .collect()
 }
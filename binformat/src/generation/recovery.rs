@@ -0,0 +1,130 @@
+use super::{bits::starts_unit, defaults::generate_field_default};
+use crate::{Item, Meta};
+use quote::{format_ident, quote};
+
+/// Generates the error type collected by `try_read` and given by `read` with `field_errors`, naming the
+/// field which failed to be read
+pub(super) fn generate_field_error(
+    root: &syn::ItemStruct,
+    meta: &Meta,
+) -> Option<proc_macro2::TokenStream> {
+    if !meta.default && !meta.field_errors {
+        return None;
+    }
+
+    let visibility = &root.vis;
+    let error_name = format_ident!("{}FieldError", root.ident);
+
+    Some(quote! {
+        /// A field which couldn't be read, and was given its default value instead
+        #[derive(Debug)]
+        #visibility struct #error_name {
+            /// Name of the field, `magic` for the magic number before the fields, or `trailer` for data
+            /// after the fields such as a checksum
+            pub field: &'static str,
+            /// Number of bytes into the data the field started at
+            pub offset: usize,
+            pub error: ::std::io::Error,
+        }
+
+        impl ::std::fmt::Display for #error_name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                write!(f, "failed to read `{}` at byte {}: {}", self.field, self.offset, self.error)
+            }
+        }
+
+        impl ::std::error::Error for #error_name {
+            fn source(&self) -> Option<&(dyn ::std::error::Error + 'static)> {
+                Some(&self.error)
+            }
+        }
+    })
+}
+
+/// Gives the locals a wrapped read has to pass out of its closure - the field itself, along with the unit
+/// if it starts a unit of bit fields
+fn read_locals(item: &Item) -> proc_macro2::TokenStream {
+    let id = &item.id;
+
+    match starts_unit(item) {
+        Some(unit) => quote! { (#id, #unit) },
+        None => quote! { #id },
+    }
+}
+
+/// Wraps the read of an item so a failure is given as an `io::Error` holding the field's name, keeping
+/// the kind of the original error
+pub(super) fn generate_traced_read(
+    read: &proc_macro2::TokenStream,
+    item: &Item,
+    error_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let id = &item.id;
+    let locals = read_locals(item);
+
+    quote! {
+        let _offset = _position.get();
+        let #locals = (|| { #read; ::std::io::Result::Ok(#locals) })().map_err(|error| {
+            ::std::io::Error::new(error.kind(), #error_name { field: stringify!(#id), offset: _offset, error })
+        })?
+    }
+}
+
+/// Wraps checks outside of the fields, like the trailer after them, so a failure is given as an `io::Error`
+/// naming them as `field`
+pub(super) fn generate_traced_checks(
+    checks: &proc_macro2::TokenStream,
+    field: &str,
+    error_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    quote! {
+        let _offset = _position.get();
+        (|| { #checks ::std::io::Result::Ok(()) })().map_err(|error| {
+            ::std::io::Error::new(error.kind(), #error_name { field: #field, offset: _offset, error })
+        })?;
+    }
+}
+
+/// Wraps the read of an item so a failure gives the default value, recording the error in `_errors`
+pub(super) fn generate_recovering_read(
+    read: &proc_macro2::TokenStream,
+    item: &Item,
+    error_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    let id = &item.id;
+    let locals = read_locals(item);
+    // later bit fields in a unit which failed to be read are taken from an empty unit
+    let default = match starts_unit(item) {
+        Some(_) => {
+            let default = generate_field_default(item);
+            quote! { (#default, 0) }
+        }
+        None => generate_field_default(item),
+    };
+
+    quote! {
+        let _offset = _position.get();
+        let #locals = match (|| { #read; ::std::io::Result::Ok(#locals) })() {
+            Ok(#locals) => #locals,
+            Err(error) => {
+                _errors.push(#error_name { field: stringify!(#id), offset: _offset, error });
+                #default
+            }
+        }
+    }
+}
+
+/// Wraps checks outside of the fields, like the trailer after them, so a failure is recorded in `_errors`
+/// under `field` rather than returned
+pub(super) fn generate_recovering_checks(
+    checks: &proc_macro2::TokenStream,
+    field: &str,
+    error_name: &syn::Ident,
+) -> proc_macro2::TokenStream {
+    quote! {
+        let _offset = _position.get();
+        if let Err(error) = (|| { #checks ::std::io::Result::Ok(()) })() {
+            _errors.push(#error_name { field: #field, offset: _offset, error });
+        }
+    }
+}
@@ -0,0 +1,244 @@
+use super::{field_name, field_name_of};
+use crate::Item;
+use proc_macro_error::abort;
+use quote::{format_ident, quote, ToTokens};
+
+/// Makes sure every reference points at a repeated table read earlier in the same struct, or one in the
+/// root context, so the generated code can assume the table is in scope.
+pub(super) fn validate_references(
+    items: &[Item],
+    root_items: &[Item],
+    struct_name: &syn::Ident,
+    is_root: bool,
+) {
+    for (index, item) in items.iter().enumerate() {
+        let Some(table) = &item.reference else {
+            if item.enum_type.is_some() {
+                abort!(struct_name, "enum `{}` must reference a table", item.id)
+            }
+            continue;
+        };
+
+        if item.repetition.is_some() {
+            abort!(struct_name, "reference `{}` cannot be repeated", item.id)
+        }
+        if item.enum_type.is_some() && item.condition.is_some() {
+            abort!(struct_name, "enum `{}` cannot be conditional", item.id)
+        }
+
+        match items[..index].iter().find(|other| &other.id == table) {
+            Some(other)
+                if item.enum_type.is_some()
+                    && other.data_type.to_token_stream().to_string() != "String" =>
+            {
+                abort!(
+                    struct_name,
+                    "table `{}` must contain strings to be used for enums",
+                    table
+                )
+            }
+            Some(other) if other.repetition.is_some() => {}
+            Some(_) => abort!(struct_name, "table `{}` must be a repeated field", table),
+            // nested types can look tables up in the root context instead
+            None if !is_root && !items.iter().any(|other| &other.id == table) => {
+                validate_root_table(item, root_items, struct_name)
+            }
+            None => abort!(
+                struct_name,
+                "table `{}` must be defined before reference `{}`",
+                table,
+                item.id
+            ),
+        }
+    }
+}
+
+/// Makes sure a reference from a nested type points at a repeated table in the root context. Enums are
+/// written by looking their string up in the table, which isn't available when writing.
+fn validate_root_table(item: &Item, root_items: &[Item], struct_name: &syn::Ident) {
+    let table = item.reference.as_ref().unwrap();
+
+    if item.enum_type.is_some() {
+        abort!(
+            struct_name,
+            "enum `{}` must reference a table in the same struct",
+            item.id
+        )
+    }
+    match root_items.iter().find(|other| &other.id == table) {
+        Some(other) if other.repetition.is_some() && other.context => {}
+        Some(other) if other.repetition.is_some() => abort!(
+            struct_name,
+            "table `{}` must be in the context (with `context: true`) to be referenced from `{}`",
+            table,
+            struct_name
+        ),
+        Some(_) => abort!(struct_name, "table `{}` must be a repeated field", table),
+        None => abort!(
+            struct_name,
+            "table `{}` must be defined before reference `{}`",
+            table,
+            item.id
+        ),
+    }
+}
+
+/// Finds the table of a reference, which is either an earlier field of the same struct or in the root
+/// context
+fn table_path(table: &syn::Ident, items: &[Item]) -> proc_macro2::TokenStream {
+    if items.iter().any(|other| &other.id == table) {
+        quote! { #table }
+    } else {
+        quote! { _root.#table }
+    }
+}
+
+/// Generates a check that the index just read is within the bounds of its table
+pub(super) fn generate_reference_check(
+    item: &Item,
+    items: &[Item],
+) -> Option<proc_macro2::TokenStream> {
+    let Item {
+        id,
+        condition,
+        reference,
+        ..
+    } = item;
+    let table = reference.as_ref()?;
+
+    // enums are checked as they're resolved
+    if item.enum_type.is_some() {
+        return None;
+    }
+    let path = table_path(table, items);
+
+    let error = quote! {
+        return Err(::std::io::Error::new(
+            ::std::io::ErrorKind::InvalidData,
+            concat!("`", stringify!(#id), "` is out of range for table `", stringify!(#table), "`"),
+        ))
+    };
+
+    // conditional references are only checked if they were actually read
+    Some(if condition.is_some() {
+        quote! {
+            if #id.is_some_and(|index| index as usize >= #path.len()) {
+                #error
+            }
+        }
+    } else {
+        quote! {
+            if #id as usize >= #path.len() {
+                #error
+            }
+        }
+    })
+}
+
+/// Wraps the read of an index so it resolves to an enum, by parsing the string at that index in the table
+pub(super) fn generate_enum_read(
+    item: &Item,
+    read: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let (Some(table), Some(enum_type)) = (&item.reference, &item.enum_type) else {
+        return read;
+    };
+    let id = &item.id;
+
+    quote! {
+        #read.and_then(|index| {
+            #table
+                .get(index as usize)
+                .ok_or_else(|| ::std::io::Error::new(
+                    ::std::io::ErrorKind::InvalidData,
+                    concat!("`", stringify!(#id), "` is out of range for table `", stringify!(#table), "`"),
+                ))?
+                .parse::<#enum_type>()
+                .map_err(|_| ::std::io::Error::new(
+                    ::std::io::ErrorKind::InvalidData,
+                    concat!("`", stringify!(#id), "` isn't a known `", stringify!(#enum_type), "`"),
+                ))
+        })
+    }
+}
+
+/// Finds the index of an enum's string in its table, so the index can be written with `write`
+/// (which should write `index` as the item's type).
+pub(super) fn generate_enum_write(
+    item: &Item,
+    items: &[Item],
+    write: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let (Some(table), Some(_)) = (&item.reference, &item.enum_type) else {
+        return write;
+    };
+    let id = &item.id;
+    let field = field_name(item);
+    let table_field = field_name_of(items, table);
+
+    quote! {
+        match self.#table_field.iter().position(|value| *value == self.#field.to_string()) {
+            Some(index) => #write,
+            None => Err(::std::io::Error::new(
+                ::std::io::ErrorKind::InvalidInput,
+                concat!("`", stringify!(#id), "` isn't in table `", stringify!(#table), "`"),
+            )),
+        }
+    }
+}
+
+/// Generates lookup helpers which resolve each reference against its table. References to a table in
+/// the root context are resolved against the root struct, which is taken as an argument.
+pub(super) fn generate_reference_accessors(
+    items: &[Item],
+    root_items: &[Item],
+    root_name: &syn::Ident,
+) -> Vec<proc_macro2::TokenStream> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let table_name = item.reference.as_ref()?;
+            if item.enum_type.is_some() {
+                return None;
+            }
+
+            let (table, in_root) = match items.iter().find(|other| &other.id == table_name) {
+                Some(table) => (table, false),
+                None => (
+                    root_items.iter().find(|other| &other.id == table_name)?,
+                    true,
+                ),
+            };
+            let owner = if in_root {
+                quote! { root }
+            } else {
+                quote! { self }
+            };
+
+            let id = field_name(item);
+            let table_name = field_name(table);
+            let data_type = &table.data_type;
+            let fn_name = format_ident!("resolve_{}", id);
+
+            let lookup = if item.condition.is_some() {
+                quote! { self.#id.and_then(|index| #owner.#table_name.get(index as usize)) }
+            } else {
+                quote! { #owner.#table_name.get(self.#id as usize) }
+            };
+
+            Some(if in_root {
+                quote! {
+                    pub fn #fn_name<'a>(&self, root: &'a #root_name) -> Option<&'a #data_type> {
+                        #lookup
+                    }
+                }
+            } else {
+                quote! {
+                    pub fn #fn_name(&self) -> Option<&#data_type> {
+                        #lookup
+                    }
+                }
+            })
+        })
+        .collect()
+}
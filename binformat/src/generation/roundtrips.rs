@@ -0,0 +1,40 @@
+use crate::Meta;
+use proc_macro_error::abort_call_site;
+use quote::{format_ident, quote};
+
+/// Generates a test reading the sample data given by `roundtrip_test`, checking writing it again gives the
+/// same bytes - which catches reads and writes which don't mirror each other
+pub(super) fn generate_roundtrip_test(
+    struct_name: &syn::Ident,
+    meta: &Meta,
+) -> Option<proc_macro2::TokenStream> {
+    let sample = meta.roundtrip_test.as_ref()?;
+    // include_bytes! is relative to the source file, so the path has to be absolute
+    let sample = std::fs::canonicalize(sample).unwrap_or_else(|_| {
+        abort_call_site!("roundtrip_test sample `{}` doesn't exist", sample.display())
+    });
+    let sample = sample.to_string_lossy();
+    let module = format_ident!("{}_roundtrip", struct_name.to_string().to_lowercase());
+
+    Some(quote! {
+        #[cfg(test)]
+        mod #module {
+            use super::*;
+
+            #[test]
+            fn sample_roundtrips() {
+                let sample: &[u8] = include_bytes!(#sample);
+
+                let value = #struct_name::from_bytes(sample).expect("sample can be read");
+                let written = value.to_bytes().expect("sample can be written");
+                assert!(
+                    written == sample,
+                    "{} doesn't write its sample back unchanged: read {:?}, wrote {:?}",
+                    stringify!(#struct_name),
+                    sample,
+                    written,
+                );
+            }
+        }
+    })
+}
@@ -0,0 +1,165 @@
+use super::{array_len, bits::unit_size, encoded_size};
+use crate::{Encoding, Item, Repetition};
+use quote::{quote, ToTokens};
+use std::collections::HashMap;
+
+/// Lower and upper bounds for the number of bytes something is encoded in
+type Bounds = (usize, Option<usize>);
+
+/// Adds two bounds together, as for two values encoded one after the other
+fn add((min_a, max_a): Bounds, (min_b, max_b): Bounds) -> Bounds {
+    (min_a + min_b, max_a.zip(max_b).map(|(a, b)| a + b))
+}
+
+/// Finds the bounds for a single value of a type, looking through composite types.
+///
+/// `visiting` tracks the composite types currently being looked through, so recursive types end up
+/// unbounded rather than looping forever.
+fn type_bounds<'a>(
+    data_type: &syn::Type,
+    types: &'a HashMap<syn::Ident, Vec<Item>>,
+    visiting: &mut Vec<&'a syn::Ident>,
+) -> Bounds {
+    if let Some(size) = encoded_size(data_type) {
+        return (size, Some(size));
+    }
+    if let syn::Type::Array(array) = data_type {
+        let Some(len) = array_len(array) else {
+            return (0, None);
+        };
+        let (min, max) = type_bounds(&array.elem, types, visiting);
+        return (min * len, max.map(|max| max * len));
+    }
+
+    let type_name = data_type.to_token_stream().to_string();
+    match types.iter().find(|(name, _)| name.to_string() == type_name) {
+        Some((name, _)) if visiting.contains(&name) => (0, None),
+        Some((name, items)) => {
+            visiting.push(name);
+            let bounds = items_bounds(items, types, visiting);
+            visiting.pop();
+            bounds
+        }
+        // types defined outside the format could be any size
+        None => (0, None),
+    }
+}
+
+/// Finds the bounds for a sequence of items
+fn items_bounds<'a>(
+    items: &'a [Item],
+    types: &'a HashMap<syn::Ident, Vec<Item>>,
+    visiting: &mut Vec<&'a syn::Ident>,
+) -> Bounds {
+    items.iter().fold((0, Some(0)), |total, item| {
+        let bounds = match &item.encoding {
+            Some(Encoding::DelimitedString(terminator)) => (terminator.len(), None),
+            Some(Encoding::LengthPrefixedString(_)) => (0, None),
+            // 64 bits take at most 10 groups of 7
+            Some(Encoding::Varint | Encoding::Varuint) => (1, Some(10)),
+            Some(Encoding::Computed(_)) => (0, Some(0)),
+            Some(Encoding::Checksum(_)) => (4, Some(4)),
+            // units of bit fields are counted at their first field
+            Some(Encoding::Bits(_)) => {
+                let size = unit_size(item, items).unwrap_or(0);
+                (size, Some(size))
+            }
+            // a union is as small as its smallest variant, and as large as its largest
+            Some(Encoding::TaggedUnion(union)) => union
+                .variants
+                .iter()
+                .map(|(_, variant)| type_bounds(&syn::parse_quote!(#variant), types, visiting))
+                .reduce(|(min_a, max_a), (min_b, max_b)| {
+                    (min_a.min(min_b), max_a.zip(max_b).map(|(a, b)| a.max(b)))
+                })
+                .unwrap_or((0, Some(0))),
+            None => type_bounds(&item.data_type, types, visiting),
+        };
+
+        // narrowed values can be as small as their narrow type
+        let bounds = match &item.narrowing {
+            Some(narrowing) => (encoded_size(&narrowing.data_type).unwrap_or(0), bounds.1),
+            None => bounds,
+        };
+
+        // absent values take up no space, unless the reader skips over them anyway
+        let bounds = match &item.condition {
+            Some(condition) if condition.advance_if_false => bounds,
+            Some(_) => (0, bounds.1),
+            None => bounds,
+        };
+
+        // repetitions can only be bounded if their count is a literal
+        let bounds = match &item.repetition {
+            Some(Repetition::Count(syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Int(count),
+                ..
+            }))) => match count.base10_parse::<usize>() {
+                Ok(count) => (bounds.0 * count, bounds.1.map(|max| max * count)),
+                Err(_) => (0, None),
+            },
+            // stored lengths take up space even if nothing is repeated
+            Some(Repetition::Footer(length_type) | Repetition::Prefix(length_type)) => {
+                (encoded_size(length_type).unwrap_or(0), None)
+            }
+            // there's always at least the sentinel
+            Some(Repetition::Until(_)) => (bounds.0, None),
+            Some(_) => (0, None),
+            None => bounds,
+        };
+
+        // asserted lengths are exact
+        let bounds = match item.assert_len {
+            Some(len) => (len, Some(len)),
+            None => bounds,
+        };
+
+        // padding and alignment can take anything up to one less than their block size
+        let bounds = match item.pad_to {
+            Some(pad_to) => add(bounds, (0, Some(pad_to - 1))),
+            None => bounds,
+        };
+        let bounds = match item.align {
+            Some(align) => add(bounds, (0, Some(align - 1))),
+            None => bounds,
+        };
+
+        // reserved space is always there
+        let skipped = item.skip_before.unwrap_or(0) + item.skip_after.unwrap_or(0);
+        let bounds = add(bounds, (skipped, Some(skipped)));
+
+        add(total, bounds)
+    })
+}
+
+/// Generates a `size_hint` function giving the minimum and (if bounded) maximum encoded size of a struct,
+/// including `framing` bytes written around its items (like a magic number or checksum), along with a
+/// `SIZE` constant if they're the same
+pub(super) fn generate_size_hint(
+    items: &[Item],
+    types: &HashMap<syn::Ident, Vec<Item>>,
+    framing: usize,
+) -> proc_macro2::TokenStream {
+    let bounds = items_bounds(items, types, &mut Vec::new());
+    let (min, max) = add(bounds, (framing, Some(framing)));
+    // structs which are always the same size have it as a constant too
+    let size = (max == Some(min)).then(|| {
+        quote! {
+            /// Number of bytes this is always encoded in
+            pub const SIZE: usize = #min;
+        }
+    });
+    let max = match max {
+        Some(max) => quote! { Some(#max) },
+        None => quote! { None },
+    };
+
+    quote! {
+        #size
+
+        /// Gives the minimum and maximum (if there is one) number of bytes this can be encoded in.
+        pub const fn size_hint() -> (usize, Option<usize>) {
+            (#min, #max)
+        }
+    }
+}
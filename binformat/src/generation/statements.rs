@@ -1,62 +1,446 @@
-use crate::{Condition, Repetition};
-use proc_macro2::TokenStream;
-use quote::quote;
+use crate::{Condition, Item, Meta, Predicate, Repetition};
+use proc_macro2::{TokenStream, TokenTree};
+use proc_macro_error::abort;
+use quote::{quote, ToTokens};
 
-use super::{reads::generate_conditional_read, writes::generate_conditional_write, Method};
+use super::{
+    endianness_of, field_name,
+    footers::{generate_footer_read, generate_footer_write},
+    is_integer, is_scalar,
+    patterns::generate_predicate,
+    reads::{generate_conditional_read, handle_simple_read},
+    writes::{generate_conditional_write, handle_simple_write},
+    Method,
+};
 
 /// Generates a conditional statement from the arguments given.
 fn generate_conditional_statement(
     condition: &Condition,
-    id: &syn::Ident,
+    item: &Item,
     statement: proc_macro2::TokenStream,
-    data_type: &syn::Type,
     method: Method,
 ) -> proc_macro2::TokenStream {
+    let data_type = &item.data_type;
     match method {
         Method::Reading => generate_conditional_read(condition, statement, data_type),
-        Method::Writing => generate_conditional_write(condition, id, statement, data_type),
+        Method::Writing => generate_conditional_write(condition, item, statement),
     }
 }
 
-/// Generates a repeated statement from the arguments given.
+/// Makes sure a field repeated until the end of the data is the last field of the root struct, which has
+/// no checksum after it, as anything following would be read as more elements. Fields repeated until a
+/// sentinel can't also be conditional, as the sentinel is checked when writing, and neither can fields
+/// with a length prefix. Repeated fields can't skip over their elements with `advance_if_false`.
+pub(super) fn validate_repeats(
+    items: &[Item],
+    meta: &Meta,
+    struct_name: &syn::Ident,
+    is_root: bool,
+) {
+    for item in items {
+        let advance_if_false = item
+            .condition
+            .as_ref()
+            .is_some_and(|condition| condition.advance_if_false);
+        if item.repetition.is_some() && advance_if_false {
+            abort!(
+                struct_name,
+                "advance_if_false can't be used on repeated fields (on `{}`)",
+                item.id
+            )
+        }
+        if matches!(item.repetition, Some(Repetition::Until(_))) && item.condition.is_some() {
+            abort!(
+                struct_name,
+                "repeating until a sentinel can't be combined with conditions (on `{}`)",
+                item.id
+            )
+        }
+        if let Some(Repetition::Prefix(length_type)) = &item.repetition {
+            if !is_integer(length_type) {
+                abort!(
+                    struct_name,
+                    "length prefix of `{}` must be an integer",
+                    item.id
+                )
+            }
+            // the length would still be written when the condition doesn't hold
+            if item.condition.is_some() {
+                abort!(
+                    struct_name,
+                    "length prefixes can't be combined with conditions (on `{}`)",
+                    item.id
+                )
+            }
+        }
+    }
+
+    let Some(index) = items
+        .iter()
+        .position(|item| matches!(item.repetition, Some(Repetition::Eof)))
+    else {
+        return;
+    };
+    let id = &items[index].id;
+
+    if !is_root || index != items.len() - 1 || items[index].skip_after.is_some() {
+        abort!(
+            struct_name,
+            "repeating until the end can only be used on the last field of the root struct (on `{}`)",
+            id
+        )
+    }
+    if meta.checksum.is_some() {
+        abort!(
+            struct_name,
+            "repeating until the end can't be combined with crc (on `{}`)",
+            id
+        )
+    }
+}
+
+/// Generates a read of elements until the end of the data. A byte is read before each element to find
+/// whether the data has ended, and then put back in front of the reader for the element to be read from -
+/// so the end of the data part way through an element is still an error.
+fn generate_eof_read(
+    statement: proc_macro2::TokenStream,
+    indexed: bool,
+) -> proc_macro2::TokenStream {
+    let index = indexed.then(|| quote! { let index = elements.len(); });
+
+    quote! {
+        (|| {
+            use ::byteorder::ReadBytesExt as _;
+
+            let mut elements = ::std::vec::Vec::new();
+            loop {
+                let first = match reader.read_u8() {
+                    Ok(byte) => [byte],
+                    Err(error) if error.kind() == ::std::io::ErrorKind::UnexpectedEof => break,
+                    Err(error) => return Err(error),
+                };
+
+                #index
+                let reader = &mut ::std::io::Read::chain(&first[..], &mut *reader);
+                elements.push(#statement?);
+            }
+
+            ::std::io::Result::Ok(elements)
+        })()
+    }
+}
+
+/// Generates a read of elements until one matches the sentinel expression, which is kept as the last
+/// element
+fn generate_until_read(
+    expr: &syn::Expr,
+    statement: proc_macro2::TokenStream,
+    indexed: bool,
+) -> proc_macro2::TokenStream {
+    let index = indexed.then(|| quote! { let index = elements.len(); });
+
+    quote! {
+        (|| {
+            let mut elements = ::std::vec::Vec::new();
+            loop {
+                #index
+                let element = #statement?;
+                let done = #expr;
+                elements.push(element);
+
+                if done {
+                    break;
+                }
+            }
+
+            ::std::io::Result::Ok(elements)
+        })()
+    }
+}
+
+/// Generates a read of the number of elements stored before them, and then that many elements
+fn generate_prefix_read(
+    length_type: &syn::Type,
+    item: &Item,
+    statement: proc_macro2::TokenStream,
+    indexed: bool,
+    meta: &Meta,
+) -> proc_macro2::TokenStream {
+    let length_read = handle_simple_read(length_type, endianness_of(item, meta), meta);
+    let index = if indexed {
+        quote! { index }
+    } else {
+        quote! { _ }
+    };
+
+    quote! {
+        (|| {
+            let count = (#length_read?) as usize;
+            (0..count).map(|#index| #statement).collect::<::std::io::Result<Vec<_>>>()
+        })()
+    }
+}
+
+/// Generates a write of the number of elements, failing if it doesn't fit in the length type, followed
+/// by every element
+fn generate_prefix_write(
+    length_type: &syn::Type,
+    item: &Item,
+    statement: proc_macro2::TokenStream,
+    indexed: bool,
+    meta: &Meta,
+) -> proc_macro2::TokenStream {
+    let id = &item.id;
+    let field = field_name(item);
+    let length_write = handle_simple_write(
+        &quote! { length },
+        length_type,
+        endianness_of(item, meta),
+        meta,
+    );
+    let write = generate_repeated_write(item, statement, indexed);
+
+    quote! {
+        (|| {
+            let length = <#length_type>::try_from(self.#field.len()).map_err(|_| {
+                ::std::io::Error::new(
+                    ::std::io::ErrorKind::InvalidInput,
+                    concat!("`", stringify!(#id), "` has too many elements for its length prefix"),
+                )
+            })?;
+            #length_write?;
+            #write
+        })()
+    }
+}
+
+/// Wraps the write of a field repeated until a sentinel so it fails unless the last element, and only
+/// the last element, matches the sentinel - otherwise it would be read back differently. Any earlier
+/// fields the expression uses are bound from `self`.
+pub(super) fn generate_until_check(
+    item: &Item,
+    previous: &[Item],
+    write: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let Some(Repetition::Until(expr)) = &item.repetition else {
+        return write;
+    };
+    let id = &item.id;
+    let field = field_name(item);
+    let bindings = bind_referenced_fields(expr, previous);
+    // scalars are compared by value, as when reading
+    let element = is_scalar(&item.data_type).then(|| quote! { let element = *element; });
+
+    quote! {
+        {
+            #bindings
+            let sentinel = self.#field.iter().position(|element| { #element #expr });
+
+            if self.#field.is_empty() || sentinel != Some(self.#field.len() - 1) {
+                Err(::std::io::Error::new(
+                    ::std::io::ErrorKind::InvalidInput,
+                    concat!("only the last element of `", stringify!(#id), "` can match its sentinel"),
+                ))
+            } else {
+                #write
+            }
+        }
+    }
+}
+
+/// Checks whether tokens refer to the context (or the version passed down with it)
+fn uses_context(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => ident == "_root" || ident == "_version",
+        TokenTree::Group(group) => uses_context(group.stream()),
+        _ => false,
+    })
+}
+
+/// Wraps the write of a repeated field with a condition so it fails if the field has elements while its
+/// condition doesn't hold - they'd be written, but skipped when read back. Any earlier fields the
+/// condition uses are bound from `self`.
+pub(super) fn generate_conditional_repetition_check(
+    item: &Item,
+    previous: &[Item],
+    write: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let (Some(condition), Some(_)) = (&item.condition, &item.repetition) else {
+        return write;
+    };
+    let expr = match &condition.predicate {
+        Predicate::Expression(expr) => expr,
+        Predicate::Match { field, .. } => field,
+        Predicate::DataRemaining => return write,
+    };
+
+    // the context only exists when reading, so conditions on it can't be checked
+    if uses_context(expr.to_token_stream()) {
+        return write;
+    }
+
+    let id = &item.id;
+    let field = field_name(item);
+    let bindings = bind_referenced_fields(expr, previous);
+    let predicate = generate_predicate(&condition.predicate);
+
+    quote! {
+        {
+            #bindings
+            if !(#predicate) && !self.#field.is_empty() {
+                Err(::std::io::Error::new(
+                    ::std::io::ErrorKind::InvalidInput,
+                    concat!("`", stringify!(#id), "` has elements, but its condition doesn't hold"),
+                ))
+            } else {
+                #write
+            }
+        }
+    }
+}
+
+/// Generates a repeated statement from the arguments given, exposing the element index as `index` if needed.
 fn generate_repeated_statement(
     repetition: &Repetition,
-    id: &syn::Ident,
+    item: &Item,
     statement: proc_macro2::TokenStream,
+    indexed: bool,
+    meta: &Meta,
     method: Method,
 ) -> proc_macro2::TokenStream {
+    let id = &item.id;
+    let field = field_name(item);
+
     match repetition {
-        Repetition::Count(expr) => match method {
-            Method::Reading => quote! {
+        Repetition::Count(expr) => match (method, indexed) {
+            (Method::Reading, false) => quote! {
                 (0..#expr).map(|_| #statement).collect::<::std::io::Result<Vec<_>>>()
             },
-            Method::Writing => quote! {
-                self.#id
-                    .iter()
-                    .map(|#id| #statement)
-                    .collect::<::std::io::Result<Vec<_>>>()
+            (Method::Reading, true) => quote! {
+                (0..#expr).map(|index| #statement).collect::<::std::io::Result<Vec<_>>>()
             },
+            (Method::Writing, _) => generate_repeated_write(item, statement, indexed),
+        },
+        Repetition::Footer(length_type) => match method {
+            Method::Reading => generate_footer_read(length_type, statement, meta),
+            Method::Writing => generate_footer_write(id, field, statement),
+        },
+        Repetition::Prefix(length_type) => match method {
+            Method::Reading => generate_prefix_read(length_type, item, statement, indexed, meta),
+            Method::Writing => generate_prefix_write(length_type, item, statement, indexed, meta),
+        },
+        Repetition::Eof => match method {
+            Method::Reading => generate_eof_read(statement, indexed),
+            Method::Writing => generate_repeated_write(item, statement, indexed),
+        },
+        Repetition::Until(expr) => match method {
+            Method::Reading => generate_until_read(expr, statement, indexed),
+            Method::Writing => generate_repeated_write(item, statement, indexed),
         },
     }
 }
 
+/// Generates a write of every element of a repeated field in turn
+fn generate_repeated_write(
+    item: &Item,
+    statement: proc_macro2::TokenStream,
+    indexed: bool,
+) -> proc_macro2::TokenStream {
+    let id = &item.id;
+    let field = field_name(item);
+
+    match indexed {
+        false => quote! {
+            self.#field
+                .iter()
+                .map(|#id| #statement)
+                .collect::<::std::io::Result<Vec<_>>>()
+        },
+        true => quote! {
+            self.#field
+                .iter()
+                .enumerate()
+                .map(|(index, #id)| #statement)
+                .collect::<::std::io::Result<Vec<_>>>()
+        },
+    }
+}
+
+/// Generates a read of a repeated field which only has elements if its condition holds, and is otherwise
+/// empty. Writes are checked by [`generate_conditional_repetition_check`] instead.
+fn generate_conditional_repetition(
+    condition: &Condition,
+    statement: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let expr = generate_predicate(&condition.predicate);
+    quote! {
+        if #expr {
+            #statement
+        } else {
+            ::std::io::Result::Ok(::std::vec::Vec::new())
+        }
+    }
+}
+
 /// Creates a final statement with all required conditional and repetition code
 pub(super) fn create_statement(
     mut original: TokenStream,
-    id: &syn::Ident,
-    data_type: &syn::Type,
-    condition: &Option<Condition>,
-    repetition: &Option<Repetition>,
+    item: &Item,
+    meta: &Meta,
     method: Method,
 ) -> proc_macro2::TokenStream {
-    // if conditional, update with required code
-    if let Some(condition) = condition {
-        original = generate_conditional_statement(condition, id, original, data_type, method);
+    let Item {
+        condition,
+        repetition,
+        ..
+    } = item;
+
+    // conditions on repeated fields decide whether there are any elements, so are applied to the whole
+    // repetition rather than each element
+    if let (Some(condition), None) = (condition, repetition) {
+        original = generate_conditional_statement(condition, item, original, method);
     }
-    // same for repetition
     if let Some(repetition) = repetition {
-        original = generate_repeated_statement(repetition, id, original, method);
+        let indexed = item.big_endian_if.is_some() || item.narrowing.is_some();
+        original = generate_repeated_statement(repetition, item, original, indexed, meta, method);
+
+        if let (Some(condition), Method::Reading) = (condition, method) {
+            original = generate_conditional_repetition(condition, original);
+        }
     }
 
     original
 }
+
+/// Binds any fields referenced in an expression to locals, so expressions written against the
+/// locals created when reading can also be evaluated when writing from `self`.
+pub(super) fn bind_referenced_fields(expr: &syn::Expr, items: &[Item]) -> TokenStream {
+    fn collect_idents(tokens: TokenStream, idents: &mut Vec<syn::Ident>) {
+        for token in tokens {
+            match token {
+                TokenTree::Ident(ident) => idents.push(ident),
+                TokenTree::Group(group) => collect_idents(group.stream(), idents),
+                _ => {}
+            }
+        }
+    }
+
+    let mut idents = Vec::new();
+    collect_idents(expr.to_token_stream(), &mut idents);
+
+    // scalars can be copied out, anything else is borrowed
+    let bindings = items
+        .iter()
+        .filter(|item| idents.contains(&item.id))
+        .map(|item| {
+            let id = &item.id;
+            let field = field_name(item);
+            if is_scalar(&item.data_type) && item.condition.is_none() && item.repetition.is_none() {
+                quote! { let #id = self.#field; }
+            } else {
+                quote! { let #id = &self.#field; }
+            }
+        });
+
+    quote! { #(#bindings)* }
+}
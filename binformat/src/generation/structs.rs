@@ -1,23 +1,178 @@
-use crate::{parse::Endianness, Item};
+use crate::{Format, Item, Meta};
 
-use super::{reads::generate_read_calls, writes::generate_write_calls, RUST_TYPES};
+use super::{
+    accessors::generate_getters,
+    bits::validate_bits,
+    checksums::{
+        checksum_size, generate_checksum_check, generate_checksum_ranges, generate_checksum_write,
+        generate_checksummed_reader, generate_checksummed_writer, validate_checksum_fields,
+    },
+    declarations::{
+        declared_fields, extra_fields, generate_extra_inits, validate_declared_derives,
+        validate_declared_fields,
+    },
+    defaults::{generate_default_derive, generate_default_impl},
+    encodings::{validate_computed, validate_strings, validate_varints},
+    equality::generate_bitwise_eq,
+    ffi::validate_ffi_safe,
+    field_name,
+    footers::{
+        generate_footer_length_write, generate_footer_skip, generate_seek_bound, validate_footers,
+    },
+    lengths::validate_lengths,
+    magic::{generate_magic_check, generate_magic_write, magic_size},
+    obfuscation::validate_ciphers,
+    patterns::validate_matches,
+    permutations::validate_permutations,
+    positions::{generate_positioned_reader, generate_positioned_writer, validate_positions},
+    reads::generate_read_calls,
+    recovery::{
+        generate_field_error, generate_recovering_checks, generate_recovering_read,
+        generate_traced_checks, generate_traced_read,
+    },
+    references::{generate_reference_accessors, validate_references},
+    sizes::generate_size_hint,
+    statements::validate_repeats,
+    trailing::{generate_optional_eof_check, validate_optional_eof},
+    unions::validate_unions,
+    widths::validate_narrowings,
+    writes::generate_write_calls,
+    Method, RUST_TYPES,
+};
 use itertools::Itertools;
+use proc_macro_error::{abort, abort_call_site};
 use quote::{format_ident, quote, ToTokens};
 
-/// Generates the root struct and assosciated context
-fn generate_root_struct(
-    root: &syn::ItemStruct,
+/// The generated pieces of a struct, shared between root and composite structs
+struct StructBody {
     types: Vec<proc_macro2::TokenStream>,
     ids: Vec<proc_macro2::TokenStream>,
+    /// Names of the struct fields, which differ from the ids of renamed items
+    fields: Vec<proc_macro2::TokenStream>,
+    /// Initialisers of each field from the local holding its value
+    inits: Vec<proc_macro2::TokenStream>,
     read_calls: Vec<proc_macro2::TokenStream>,
     write_calls: Vec<proc_macro2::TokenStream>,
+    methods: Vec<proc_macro2::TokenStream>,
+    positioned_reader: Option<proc_macro2::TokenStream>,
+    positioned_writer: Option<proc_macro2::TokenStream>,
+}
+
+/// Generates the root struct and assosciated context
+fn generate_root_struct(
+    root: &syn::ItemStruct,
+    meta: &Meta,
+    items: &[Item],
+    body: StructBody,
 ) -> proc_macro2::TokenStream {
+    let StructBody {
+        types,
+        ids,
+        fields,
+        inits,
+        read_calls,
+        write_calls,
+        methods,
+        positioned_reader,
+        positioned_writer,
+    } = body;
     let struct_name = &root.ident;
     let visibility = &root.vis;
+    let Meta {
+        read_fn, write_fn, ..
+    } = meta;
+    let repr = meta.repr_c.then(|| quote! { #[repr(C)] });
+    let derives = generate_derives(meta, true);
+    let serde = generate_serde_derive(meta);
+    let field_visibility = generate_field_visibility(meta);
+    let declared = declared_fields(root).is_some();
+    let extra_fields = extra_fields(root, items);
+    let default_derive = generate_default_derive(items, meta);
+    let default_impl = generate_default_impl(
+        struct_name,
+        items,
+        meta,
+        declared.then_some(extra_fields.as_slice()),
+    );
+    let bitwise_eq = generate_bitwise_eq(struct_name, items, meta, &extra_fields);
 
     // if is root, construct a struct context with all simple types before first complex type
     let context_name = format_ident!("{}Context", struct_name);
 
+    // checksums cover everything read or written by the root struct
+    let checksummed_reader = generate_checksummed_reader(meta);
+    let checksum_check = generate_checksum_check(meta);
+    let checksummed_writer = generate_checksummed_writer(meta);
+    let checksum_write = generate_checksum_write(meta);
+
+    // as do checksum fields, over the bytes in their range
+    let checksum_ranges_reader = generate_checksum_ranges(items, Method::Reading);
+    let checksum_ranges_writer = generate_checksum_ranges(items, Method::Writing);
+
+    // and start with the magic number, if there is one
+    let magic_check = generate_magic_check(meta);
+    let magic_write = generate_magic_write(meta);
+
+    // lengths stored in the footer are read by seeking
+    let seek_bound = generate_seek_bound(items);
+    let footer_skip = generate_footer_skip(items);
+    let footer_length_write = generate_footer_length_write(items, meta);
+
+    // trailing fields missing from the data can only be followed by more missing fields
+    let optional_eof_check = generate_optional_eof_check(items);
+
+    // a version given to `read` is passed down with the context, as `_version`
+    if meta.version_param && (meta.debug_assert_roundtrip || meta.roundtrip_test.is_some()) {
+        abort_call_site!("version_param can't be combined with debug_assert_roundtrip or roundtrip_test, as they read without a version")
+    }
+    let (version_param, version_arg) = meta
+        .version_param
+        .then(|| (quote! { , _version: u16 }, quote! { , _version }))
+        .unzip();
+    let version_init = meta.version_param.then(|| quote! { _version, });
+
+    // the root struct is the top of any nesting
+    let depth = meta.max_depth.map(|_| quote! { let _depth: usize = 0; });
+
+    // debug builds can check the written bytes read back to the same value, catching asymmetric formats
+    let roundtrip_check = meta.debug_assert_roundtrip.then(|| {
+        quote! {
+            #[cfg(debug_assertions)]
+            {
+                let read = Self::#read_fn(&mut bytes.as_slice());
+                debug_assert!(
+                    read.as_ref().is_ok_and(|read| read == self),
+                    "{} doesn't read back to the value it was written from: wrote {:?}, read {:?}",
+                    stringify!(#struct_name),
+                    self,
+                    read,
+                );
+            }
+        }
+    });
+
+    // a struct declaring its own fields is kept as it was written, including its derives
+    let definition = if declared {
+        quote! { #root }
+    } else {
+        quote! {
+            #derives
+            #default_derive
+            #serde
+            #repr
+            #visibility struct #struct_name {
+                #(#field_visibility #fields: #types),*
+            }
+        }
+    };
+
+    let schema_version = meta.version.map(|version| {
+        quote! {
+            /// Version of the format this struct was generated from
+            pub const SCHEMA_VERSION: u16 = #version;
+        }
+    });
+
     /// Helper function to figure out if a type is "simple" - not a composite type
     fn is_simple_type(data_type: &proc_macro2::TokenStream) -> bool {
         // check if list of rust types contains it
@@ -28,48 +183,241 @@ fn generate_root_struct(
     let simple_types: Vec<_> = types.iter().take_while_ref(|t| is_simple_type(t)).collect();
     let simple_ids: Vec<_> = ids.iter().take(simple_types.len()).collect();
 
+    // later fields can be opted into the context too, which is updated to borrow them once they're read
+    let context_items: Vec<_> = items
+        .iter()
+        .skip(simple_types.len())
+        .filter(|item| item.context)
+        .collect();
+    let context_ids: Vec<_> = context_items.iter().map(|item| &item.id).collect();
+    let field_name = format_ident!("{}ContextField", struct_name);
+    let context = generate_context(root, &simple_ids, &simple_types, &context_items, meta);
+
     // then split the read calls at the same point so context struct can be inserted in the middle
-    let initial_read_calls = read_calls.iter().take(simple_types.len());
-    let rest_read_calls = read_calls.iter().skip(simple_types.len());
+    let read_body = |read_calls: Vec<proc_macro2::TokenStream>, header, trailer| {
+        let initial_read_calls = read_calls.iter().take(simple_types.len());
+        let rest_read_calls =
+            read_calls
+                .iter()
+                .zip(items)
+                .skip(simple_types.len())
+                .map(|(read, item)| {
+                    let update = generate_context_update(struct_name, item);
+                    quote! { #read; #update }
+                });
 
-    quote! {
-        #visibility struct #context_name {
-            #(pub #simple_ids: #simple_types),*
+        quote! {
+            #checksummed_reader
+            #checksum_ranges_reader
+            #positioned_reader
+            #depth
+            #header
+
+            #(
+                #initial_read_calls;
+            )*
+
+            let _root = #context_name {
+                #version_init
+                #(#simple_ids,)*
+                #(#context_ids: #field_name { name: stringify!(#context_ids), value: None }),*
+            };
+
+            #(
+                #rest_read_calls;
+            )*
+
+            #trailer
         }
+    };
+    let trailer = quote! { #footer_skip #checksum_check };
 
-        #[derive(Debug, PartialEq)]
-        #visibility struct #struct_name {
-            #(pub #ids: #types),*
+    // with defaults available, reads can recover from failures by defaulting the fields which failed
+    let field_error = generate_field_error(root, meta);
+    let try_read = meta.default.then(|| {
+        let error_name = format_ident!("{}FieldError", struct_name);
+        let try_read_fn = format_ident!("try_{}", read_fn);
+        let recovering_reads = read_calls
+            .iter()
+            .zip(items)
+            .map(|(read, item)| generate_recovering_read(read, item, &error_name))
+            .collect();
+        let recovering_header = magic_check
+            .as_ref()
+            .map(|check| generate_recovering_checks(check, "magic", &error_name));
+        let recovering_trailer = generate_recovering_checks(&trailer, "trailer", &error_name);
+        let body = read_body(recovering_reads, recovering_header, recovering_trailer);
+
+        quote! {
+            /// Reads as much as possible, giving fields which fail to be read their default value and
+            /// returning the errors for each of them
+            pub fn #try_read_fn<R: ::byteorder::ReadBytesExt #seek_bound>(reader: &mut R #version_param) -> (Self, ::std::vec::Vec<#error_name>) {
+                let mut _errors = ::std::vec::Vec::new();
+                #body
+
+                (Self { #(#inits),* }, _errors)
+            }
         }
+    });
+    // the number of bytes read can be given alongside the struct, by counting them as they're read
+    let counted_fn = format_ident!("{}_counted", read_fn);
+    let counting_reader = generate_positioned_reader(items, true);
 
-        impl #struct_name {
-            pub fn read<R: ::byteorder::ReadBytesExt>(reader: &mut R) -> ::std::io::Result<Self> {
-                #(
-                    #initial_read_calls;
-                )*
+    // otherwise with field errors, a failed read names the field it failed on
+    let read_body = if meta.field_errors {
+        let error_name = format_ident!("{}FieldError", struct_name);
+        let traced_reads = read_calls
+            .iter()
+            .zip(items)
+            .map(|(read, item)| generate_traced_read(read, item, &error_name))
+            .collect();
+        let traced_header = magic_check
+            .as_ref()
+            .map(|check| generate_traced_checks(check, "magic", &error_name));
+        let traced_trailer = generate_traced_checks(&trailer, "trailer", &error_name);
+        read_body(traced_reads, traced_header, traced_trailer)
+    } else {
+        read_body(read_calls, magic_check, trailer)
+    };
 
-                let _root = #context_name {
-                    #(#simple_ids),*
-                };
+    quote! {
+        #context
+        #field_error
 
-                #(
-                    #rest_read_calls;
-                )*
+        #definition
+
+        #default_impl
+        #bitwise_eq
+
+        impl #struct_name {
+            #schema_version
+
+            pub fn #read_fn<R: ::byteorder::ReadBytesExt #seek_bound>(reader: &mut R #version_param) -> ::std::io::Result<Self> {
+                #read_body
 
                 Ok(Self {
-                    #(#ids),*
+                    #(#inits),*
                 })
             }
 
-            pub fn write<W: ::byteorder::WriteBytesExt>(&self, writer: &mut W) -> ::std::io::Result<()> {
+            #try_read
+
+            /// Reads this along with the number of bytes it took, which works for any reader
+            pub fn #counted_fn<R: ::byteorder::ReadBytesExt #seek_bound>(reader: &mut R #version_param) -> ::std::io::Result<(Self, usize)> {
+                #counting_reader
+                let value = Self::#read_fn(reader #version_arg)?;
+
+                Ok((value, _position.get()))
+            }
+
+            pub fn #write_fn<W: ::byteorder::WriteBytesExt>(&self, writer: &mut W) -> ::std::io::Result<()> {
+                #optional_eof_check
+                #checksummed_writer
+                #checksum_ranges_writer
+                #positioned_writer
+                #magic_write
+
                 #(
                     #write_calls;
                 )*
 
+                #footer_length_write
+                #checksum_write
+
                 Ok(())
             }
+
+            /// Reads this from the start of a slice, ignoring anything after it
+            pub fn from_bytes(data: &[u8] #version_param) -> ::std::io::Result<Self> {
+                Self::#read_fn(&mut ::std::io::Cursor::new(data) #version_arg)
+            }
+
+            /// Writes this to a new `Vec`
+            pub fn to_bytes(&self) -> ::std::io::Result<::std::vec::Vec<u8>> {
+                let mut bytes = ::std::vec::Vec::new();
+                self.#write_fn(&mut bytes)?;
+                #roundtrip_check
+
+                Ok(bytes)
+            }
+
+            #(#methods)*
+        }
+    }
+}
+
+/// Generates the context of the root struct, holding the leading simple fields along with any later fields
+/// opted into the context (which are borrowed, and only available once they've been read)
+fn generate_context(
+    root: &syn::ItemStruct,
+    simple_ids: &[&proc_macro2::TokenStream],
+    simple_types: &[&proc_macro2::TokenStream],
+    context_items: &[&Item],
+    meta: &Meta,
+) -> proc_macro2::TokenStream {
+    let visibility = &root.vis;
+    let context_name = format_ident!("{}Context", root.ident);
+    let version = meta.version_param.then(|| quote! { pub _version: u16, });
+
+    if context_items.is_empty() {
+        return quote! {
+            #visibility struct #context_name {
+                #version
+                #(pub #simple_ids: #simple_types),*
+            }
+        };
+    }
+
+    let field_name = format_ident!("{}ContextField", root.ident);
+    let context_ids = context_items.iter().map(|item| &item.id);
+    let context_types = context_items.iter().map(|item| field_type(item));
+
+    quote! {
+        /// A field of the context which is only available once it's been read
+        #visibility struct #field_name<'a, T> {
+            name: &'static str,
+            value: Option<&'a T>,
         }
+
+        impl<T> ::std::ops::Deref for #field_name<'_, T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                self.value.unwrap_or_else(|| {
+                    panic!("`{}` was used from the context before it was read", self.name)
+                })
+            }
+        }
+
+        #visibility struct #context_name<'a> {
+            #version
+            #(pub #simple_ids: #simple_types,)*
+            #(pub #context_ids: #field_name<'a, #context_types>),*
+        }
+    }
+}
+
+/// Generates the update to the root context after an item opted into it has been read
+fn generate_context_update(
+    root_name: &syn::Ident,
+    item: &Item,
+) -> Option<proc_macro2::TokenStream> {
+    if !item.context {
+        return None;
     }
+
+    let id = &item.id;
+    let context_name = format_ident!("{}Context", root_name);
+    let field_name = format_ident!("{}ContextField", root_name);
+
+    // the context can be made up of only this field, leaving nothing for the update to fill in
+    Some(quote! {
+        #[allow(clippy::needless_update)]
+        let _root = #context_name {
+            #id: #field_name { name: stringify!(#id), value: Some(&#id) },
+            .._root
+        }
+    })
 }
 
 /// Generates a composite struct for user defined types
@@ -77,89 +425,237 @@ fn generate_composite_struct(
     struct_name: &syn::Ident,
     root_name: &syn::Ident,
     visibility: &syn::Visibility,
-    types: Vec<proc_macro2::TokenStream>,
-    ids: Vec<proc_macro2::TokenStream>,
-    read_calls: Vec<proc_macro2::TokenStream>,
-    write_calls: Vec<proc_macro2::TokenStream>,
+    meta: &Meta,
+    items: &[Item],
+    body: StructBody,
 ) -> proc_macro2::TokenStream {
+    let StructBody {
+        types,
+        ids: _,
+        fields,
+        inits,
+        read_calls,
+        write_calls,
+        methods,
+        positioned_reader,
+        positioned_writer,
+    } = body;
     let context_name = format_ident!("{}Context", root_name);
+    let Meta {
+        read_fn, write_fn, ..
+    } = meta;
+    let repr = meta.repr_c.then(|| quote! { #[repr(C)] });
+    let derives = generate_derives(meta, true);
+    let serde = generate_serde_derive(meta);
+    let field_visibility = generate_field_visibility(meta);
+    let default_derive = generate_default_derive(items, meta);
+    let default_impl = generate_default_impl(struct_name, items, meta, None);
+    let bitwise_eq = generate_bitwise_eq(struct_name, items, meta, &[]);
+
+    // the version is available to expressions as `_version`, like in the root struct
+    let version_binding = meta
+        .version_param
+        .then(|| quote! { let _version = _root._version; });
+
+    // nested types are given their depth, so deeply nested data can be rejected before overflowing the stack
+    let (depth_param, depth_check) = match meta.max_depth {
+        Some(max_depth) => (
+            quote! { , _depth: usize },
+            quote! {
+                if _depth > #max_depth {
+                    return Err(::std::io::Error::new(
+                        ::std::io::ErrorKind::InvalidData,
+                        concat!("`", stringify!(#struct_name), "` is nested deeper than ", #max_depth),
+                    ));
+                }
+            },
+        ),
+        None => (quote! {}, quote! {}),
+    };
 
     quote! {
-        #[derive(Debug, PartialEq)]
+        #derives
+        #default_derive
+        #serde
+        #repr
         #visibility struct #struct_name {
-            #(pub #ids: #types),*
+            #(#field_visibility #fields: #types),*
         }
 
+        #default_impl
+        #bitwise_eq
+
         impl #struct_name {
-            pub fn read<R: ::byteorder::ReadBytesExt>(reader: &mut R, _root: &#context_name) -> ::std::io::Result<Self> {
+            pub fn #read_fn<R: ::byteorder::ReadBytesExt>(reader: &mut R, _root: &#context_name #depth_param) -> ::std::io::Result<Self> {
+                #depth_check
+                #version_binding
+                #positioned_reader
+
                 #(
                     #read_calls;
                 )*
 
                 Ok(Self {
-                    #(#ids),*
+                    #(#inits),*
                 })
             }
 
-            pub fn write<W: ::byteorder::WriteBytesExt>(&self, writer: &mut W) -> ::std::io::Result<()> {
+            pub fn #write_fn<W: ::byteorder::WriteBytesExt>(&self, writer: &mut W) -> ::std::io::Result<()> {
+                #positioned_writer
+
                 #(
                     #write_calls;
                 )*
 
                 Ok(())
             }
+
+            #(#methods)*
         }
     }
 }
 
+/// Gives the visibility of generated fields, which are private when they have getters instead
+fn generate_field_visibility(meta: &Meta) -> Option<proc_macro2::TokenStream> {
+    (!meta.getters).then(|| quote! { pub })
+}
+
+/// Derives the traits every generated type has, along with any extra ones listed in `derives`.
+/// `PartialEq` is left out of structs when it's implemented bitwise instead.
+pub(super) fn generate_derives(meta: &Meta, is_struct: bool) -> proc_macro2::TokenStream {
+    let derives = &meta.derives;
+    let partial_eq = (!(is_struct && meta.bitwise_eq)).then(|| quote! { , PartialEq });
+    quote! { #[derive(Debug, Clone #partial_eq #(, #derives)*)] }
+}
+
+/// Derives serde's traits on generated structs if enabled, which needs `serde` as a dependency of the
+/// crate using the format
+pub(super) fn generate_serde_derive(meta: &Meta) -> Option<proc_macro2::TokenStream> {
+    meta.serde
+        .then(|| quote! { #[derive(::serde::Serialize, ::serde::Deserialize)] })
+}
+
+/// Finds the type of the struct field generated for an item, wrapping it if repeated or conditional
+pub(super) fn field_type(item: &Item) -> proc_macro2::TokenStream {
+    let Item {
+        data_type,
+        repetition,
+        condition,
+        ..
+    } = item;
+
+    // enums are stored as the resolved value rather than the index
+    let data_type = item.enum_type.as_ref().unwrap_or(data_type);
+
+    match (repetition, condition) {
+        (Some(_), _) => syn::parse_str(&format!("Vec<{}>", data_type.into_token_stream())).unwrap(),
+        (None, Some(_)) => {
+            syn::parse_str(&format!("Option<{}>", data_type.into_token_stream())).unwrap()
+        }
+        _ => quote! { #data_type },
+    }
+}
+
 /// Generate a struct with given information with read implementation, correctly handling the root case.
 pub(super) fn generate_struct(
     root: &syn::ItemStruct,
     struct_name: &syn::Ident,
-    endianness: Endianness,
+    format: &Format,
     items: &[Item],
 ) -> proc_macro2::TokenStream {
     let root_name = &root.ident;
+    let meta = &format.meta;
 
     // extract a list of types and ids from the item slice
     // needs to be two arrays because of how quote handles iterating
-    let types: Vec<_> = items
+    let types: Vec<_> = items.iter().map(field_type).collect();
+    let ids: Vec<_> = items.iter().map(|Item { id, .. }| quote! { #id}).collect();
+    let fields: Vec<_> = items
         .iter()
-        .map(
-            |Item {
-                 data_type,
-                 repetition,
-                 condition,
-                 ..
-             }| match (repetition, condition) {
-                (Some(_), _) => {
-                    syn::parse_str(&format!("Vec<{}>", data_type.into_token_stream())).unwrap()
-                }
-                (None, Some(_)) => {
-                    syn::parse_str(&format!("Option<{}>", data_type.into_token_stream())).unwrap()
-                }
-                _ => quote! { #data_type },
-            },
-        )
+        .map(|item| field_name(item).to_token_stream())
         .collect();
-    let ids: Vec<_> = items.iter().map(|Item { id, .. }| quote! { #id}).collect();
+    let mut inits: Vec<_> = items
+        .iter()
+        .map(|item| match &item.rename {
+            Some(rename) => {
+                let id = &item.id;
+                quote! { #rename: #id }
+            }
+            None => item.id.to_token_stream(),
+        })
+        .collect();
+    // a root struct declaring its own fields can have more than the format, which are defaulted
+    if struct_name == root_name {
+        validate_declared_fields(root, items);
+        validate_declared_derives(root, meta);
+        inits.extend(generate_extra_inits(root, items));
+    }
+
+    // only the root struct has a context
+    if let Some(item) = items
+        .iter()
+        .find(|item| item.context && struct_name != root_name)
+    {
+        abort!(
+            struct_name,
+            "only fields of the root struct can be in the context (on `{}`)",
+            item.id
+        )
+    }
+
+    if meta.repr_c {
+        validate_ffi_safe(items, &format.types, struct_name);
+    }
 
     // then generate the list of calls
-    let read_calls = generate_read_calls(items, endianness, struct_name);
-    let write_calls = generate_write_calls(items, endianness, struct_name);
+    validate_footers(items, meta, struct_name, struct_name == root_name);
+    validate_repeats(items, meta, struct_name, struct_name == root_name);
+    validate_optional_eof(items, meta, struct_name, struct_name == root_name);
+    validate_checksum_fields(items, struct_name, struct_name == root_name);
+    validate_references(items, &format.items, struct_name, struct_name == root_name);
+    validate_ciphers(items, struct_name);
+    validate_narrowings(items, struct_name);
+    validate_lengths(items, struct_name);
+    validate_strings(items, struct_name);
+    validate_varints(items, struct_name);
+    validate_computed(items, struct_name);
+    validate_bits(items, struct_name);
+    validate_unions(items, format, struct_name);
+    validate_matches(items, struct_name);
+    validate_permutations(items, struct_name);
+    validate_positions(items, struct_name);
+    let read_calls = generate_read_calls(items, format, struct_name);
+    let write_calls = generate_write_calls(items, format, struct_name);
+    let mut methods = generate_reference_accessors(items, &format.items, root_name);
+    methods.extend(generate_getters(items, meta, struct_name));
+    // the root struct is framed by its magic number and checksum, if it has them
+    let framing = if struct_name == root_name {
+        magic_size(meta) + checksum_size(meta)
+    } else {
+        0
+    };
+    methods.push(generate_size_hint(items, &format.types, framing));
+
+    let body = StructBody {
+        types,
+        ids,
+        fields,
+        inits,
+        read_calls,
+        write_calls,
+        methods,
+        // failed fields of the root struct are reported with the offset they started at
+        positioned_reader: generate_positioned_reader(
+            items,
+            struct_name == root_name && (meta.default || meta.field_errors),
+        ),
+        positioned_writer: generate_positioned_writer(items),
+    };
 
     // simple check for root struct
     if struct_name == root_name {
-        generate_root_struct(root, types, ids, read_calls, write_calls)
+        generate_root_struct(root, meta, items, body)
     } else {
-        generate_composite_struct(
-            struct_name,
-            root_name,
-            &root.vis,
-            types,
-            ids,
-            read_calls,
-            write_calls,
-        )
+        generate_composite_struct(struct_name, root_name, &root.vis, meta, items, body)
     }
 }
@@ -0,0 +1,118 @@
+use super::{field_name, footers::footer_item};
+use crate::{Condition, Item, Meta, Predicate, Repetition};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Checks whether an item is only read if there's data left
+fn is_optional_eof(item: &Item) -> bool {
+    matches!(
+        item.condition,
+        Some(Condition {
+            predicate: Predicate::DataRemaining,
+            ..
+        })
+    )
+}
+
+/// Makes sure fields which can be missing from the end of the data are at the end of the root struct, so
+/// once one is missing every field after it is too
+pub(super) fn validate_optional_eof(
+    items: &[Item],
+    meta: &Meta,
+    struct_name: &syn::Ident,
+    is_root: bool,
+) {
+    let Some(index) = items.iter().position(is_optional_eof) else {
+        return;
+    };
+    let id = &items[index].id;
+
+    if !is_root {
+        abort!(
+            struct_name,
+            "optional_eof can only be used in the root struct (on `{}`)",
+            id
+        )
+    }
+    // an element repeated until the end is just as happy to be missing
+    if let Some(item) = items[index..]
+        .iter()
+        .find(|item| !is_optional_eof(item) && !matches!(item.repetition, Some(Repetition::Eof)))
+    {
+        abort!(
+            struct_name,
+            "fields after one with optional_eof must also have it (on `{}`)",
+            item.id
+        )
+    }
+    // the end of the data has to be the end of these fields, rather than a checksum or footer
+    if meta.checksum.is_some() || footer_item(items).is_some() {
+        abort!(
+            struct_name,
+            "optional_eof can't be combined with crc or footer lengths (on `{}`)",
+            id
+        )
+    }
+
+    for item in items.iter().filter(|item| is_optional_eof(item)) {
+        // anything read before the field would hit the end of the data first
+        if item.repetition.is_some()
+            || item
+                .condition
+                .as_ref()
+                .is_some_and(|condition| condition.advance_if_false)
+            || item.align.is_some()
+            || item.skip_before.is_some()
+        {
+            abort!(
+                struct_name,
+                "optional_eof can't be combined with repeats, advance_if_false, align or skip (on `{}`)",
+                item.id
+            )
+        }
+    }
+}
+
+/// Generates a read of a field only if there's data left. A byte is read to find whether the data has
+/// ended, and then put back in front of the reader for the field to be read from - so the end of the data
+/// part way through the field is still an error.
+pub(super) fn generate_optional_eof_read(
+    statement: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        (|| {
+            use ::byteorder::ReadBytesExt as _;
+
+            let first = match reader.read_u8() {
+                Ok(byte) => [byte],
+                Err(error) if error.kind() == ::std::io::ErrorKind::UnexpectedEof => return Ok(None),
+                Err(error) => return Err(error),
+            };
+
+            let reader = &mut ::std::io::Read::chain(&first[..], &mut *reader);
+            ::std::io::Result::Ok(Some(#statement?))
+        })()?
+    }
+}
+
+/// Generates a check that no field which can be missing from the end of the data is written after one
+/// which is missing, as it would be read back in the wrong place
+pub(super) fn generate_optional_eof_check(items: &[Item]) -> proc_macro2::TokenStream {
+    let optional: Vec<_> = items.iter().filter(|item| is_optional_eof(item)).collect();
+
+    let checks = optional.windows(2).map(|pair| {
+        let (missing, present) = (&pair[0].id, &pair[1].id);
+        let (missing_field, present_field) = (field_name(pair[0]), field_name(pair[1]));
+
+        quote! {
+            if self.#missing_field.is_none() && self.#present_field.is_some() {
+                return Err(::std::io::Error::new(
+                    ::std::io::ErrorKind::InvalidInput,
+                    concat!("`", stringify!(#present), "` can't be written without `", stringify!(#missing), "`"),
+                ));
+            }
+        }
+    });
+
+    quote! { #(#checks)* }
+}
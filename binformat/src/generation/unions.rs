@@ -0,0 +1,149 @@
+use super::{
+    field_name_of,
+    reads::handle_simple_read,
+    structs::{generate_derives, generate_serde_derive},
+    validate_prior_integer,
+    writes::handle_simple_write,
+};
+use crate::{Encoding, Format, Item, Meta, Union};
+use proc_macro2::Literal;
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Makes sure the tag of every tagged union is a plain integer read earlier in the same struct, and that
+/// each of its variants is a type from the format.
+pub(super) fn validate_unions(items: &[Item], format: &Format, struct_name: &syn::Ident) {
+    for (index, item) in items.iter().enumerate() {
+        let Some(Encoding::TaggedUnion(union)) = &item.encoding else {
+            continue;
+        };
+
+        validate_prior_integer(items, index, &union.tag, "tag", struct_name);
+        for (_, variant) in &union.variants {
+            if !format.types.contains_key(variant) {
+                abort!(
+                    struct_name,
+                    "variant `{}` of `{}` must be a type defined in the format",
+                    variant,
+                    union.name
+                )
+            }
+        }
+    }
+}
+
+/// Generates the enum for every tagged union in the format, with a variant named after each of its types
+pub(super) fn generate_unions(root: &syn::ItemStruct, format: &Format) -> proc_macro2::TokenStream {
+    let visibility = &root.vis;
+    let derives = generate_derives(&format.meta, false);
+    let serde = generate_serde_derive(&format.meta);
+
+    let mut names = Vec::new();
+    let unions = format
+        .items
+        .iter()
+        .chain(format.types.values().flatten())
+        .filter_map(|item| match &item.encoding {
+            Some(Encoding::TaggedUnion(union)) => Some(union),
+            _ => None,
+        })
+        .map(|union| {
+            let Union { name, variants, .. } = union;
+            if names.contains(&name) {
+                abort!(root, "enum `{}` is defined more than once", name)
+            }
+            names.push(name);
+
+            let variants: Vec<_> = variants.iter().map(|(_, variant)| variant).collect();
+
+            // variants can't derive a default, so the first is used
+            let default = format.meta.default.then(|| {
+                let first = variants[0];
+                quote! {
+                    impl ::std::default::Default for #name {
+                        fn default() -> Self {
+                            #name::#first(::std::default::Default::default())
+                        }
+                    }
+                }
+            });
+
+            quote! {
+                #derives
+                #serde
+                #visibility enum #name {
+                    #(#variants(#variants)),*
+                }
+
+                #default
+            }
+        })
+        .collect::<Vec<_>>();
+
+    quote! { #(#unions)* }
+}
+
+/// Generates a read of a tagged union, reading the variant its tag selects and evaluating to an
+/// `io::Result`
+pub(super) fn generate_union_read(union: &Union, meta: &Meta) -> proc_macro2::TokenStream {
+    let Union {
+        name,
+        tag,
+        variants,
+    } = union;
+
+    let arms = variants.iter().map(|(value, variant)| {
+        let value = Literal::u64_unsuffixed(*value);
+        let read = handle_simple_read(&syn::parse_quote!(#variant), meta.endianness, meta);
+        quote! { #value => #read.map(#name::#variant) }
+    });
+
+    quote! {
+        match #tag {
+            #(#arms,)*
+            _ => Err(::std::io::Error::new(
+                ::std::io::ErrorKind::InvalidData,
+                concat!("unknown tag for `", stringify!(#name), "`"),
+            )),
+        }
+    }
+}
+
+/// Generates a write of a tagged union, making sure the variant written matches the stored tag and
+/// evaluating to an `io::Result`
+pub(super) fn generate_union_write(
+    value: &proc_macro2::TokenStream,
+    union: &Union,
+    item: &Item,
+    items: &[Item],
+    meta: &Meta,
+) -> proc_macro2::TokenStream {
+    let Union {
+        name,
+        tag,
+        variants,
+    } = union;
+    let id = &item.id;
+    let field = field_name_of(items, tag);
+
+    let arms = variants.iter().map(|(tag_value, variant)| {
+        let tag_value = Literal::u64_unsuffixed(*tag_value);
+        let write = handle_simple_write(
+            &quote! { value },
+            &syn::parse_quote!(#variant),
+            meta.endianness,
+            meta,
+        );
+        quote! { #name::#variant(value) if self.#field as u64 == #tag_value => #write }
+    });
+
+    quote! {
+        match &#value {
+            #(#arms,)*
+            _ => Err(::std::io::Error::new(
+                ::std::io::ErrorKind::InvalidInput,
+                concat!("`", stringify!(#id), "` doesn't match its tag `", stringify!(#tag), "`"),
+            )),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use super::{encoded_size, is_integer};
+use crate::{Item, Narrowing};
+use proc_macro_error::abort;
+use quote::quote;
+
+/// Makes sure every narrowed item is an integer stored as a smaller integer, read directly by the reader
+pub(super) fn validate_narrowings(items: &[Item], struct_name: &syn::Ident) {
+    for item in items {
+        let Some(narrowing) = &item.narrowing else {
+            continue;
+        };
+
+        if item.encoding.is_some() || item.big_endian_if.is_some() || item.layout.is_some() {
+            abort!(
+                struct_name,
+                "narrow_type can't be combined with encodings, big_endian_if or layouts (on `{}`)",
+                item.id
+            )
+        }
+        if !is_integer(&item.data_type)
+            || !is_integer(&narrowing.data_type)
+            || encoded_size(&narrowing.data_type) >= encoded_size(&item.data_type)
+        {
+            abort!(
+                struct_name,
+                "narrow_type must be a smaller integer than the type of `{}`",
+                item.id
+            )
+        }
+    }
+}
+
+/// Chooses between reading the narrow type (widening it into the item's type) and the item's own type
+pub(super) fn generate_narrowed_read(
+    narrowing: &Narrowing,
+    data_type: &syn::Type,
+    narrow_read: proc_macro2::TokenStream,
+    read: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let Narrowing { expression, .. } = narrowing;
+
+    quote! {
+        if #expression {
+            #narrow_read.map(<#data_type>::from)
+        } else {
+            #read
+        }
+    }
+}
+
+/// Chooses between writing the value as the narrow type (available to `narrow_write` as `narrowed`)
+/// and as the item's own type, erroring if the value doesn't fit in the narrow type.
+pub(super) fn generate_narrowed_write(
+    narrowing: &Narrowing,
+    id: &syn::Ident,
+    value: &proc_macro2::TokenStream,
+    bindings: proc_macro2::TokenStream,
+    narrow_write: proc_macro2::TokenStream,
+    write: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let Narrowing {
+        data_type,
+        expression,
+    } = narrowing;
+
+    quote! {
+        {
+            #bindings
+            if #expression {
+                match <#data_type>::try_from(#value) {
+                    Ok(narrowed) => #narrow_write,
+                    Err(_) => Err(::std::io::Error::new(
+                        ::std::io::ErrorKind::InvalidInput,
+                        concat!("`", stringify!(#id), "` is too large for `", stringify!(#data_type), "`"),
+                    )),
+                }
+            } else {
+                #write
+            }
+        }
+    }
+}
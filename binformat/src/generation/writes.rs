@@ -1,27 +1,64 @@
-use super::RUST_TYPES;
+use super::{encoded_size, endianness_of, field_name, is_supported_type, RUST_TYPES};
 use crate::{
-    generation::{statements::create_statement, Method},
+    generation::{
+        bits::generate_bits_write,
+        checksums::generate_checksum_field_write,
+        encodings::generate_encoded_write,
+        is_scalar,
+        layouts::generate_soa_write,
+        lengths::generate_length_write,
+        obfuscation::generate_enciphered_write,
+        permutations::generate_permuted_write,
+        positions::generate_positioned_write,
+        references::generate_enum_write,
+        statements::{
+            bind_referenced_fields, create_statement, generate_conditional_repetition_check,
+            generate_until_check,
+        },
+        widths::generate_narrowed_write,
+        Method,
+    },
     parse::Endianness,
-    Condition, Item,
+    Condition, Encoding, Format, Item, Meta, Repetition,
 };
 use proc_macro_error::abort;
 use quote::{format_ident, quote, ToTokens};
-use syn::{Type, TypePath};
 
-/// Creates simple write code for the following 3 cases:
+/// Creates simple write code for the following 5 cases:
 ///     - Simple rust types like u16 where can just call writer function with correct endianness
-///     - Booleans where need to do a simple conversion
+///     - Booleans and characters where need to do a simple conversion
+///     - Tuples where each element is written in order
+///     - Arrays where each element is written in order
 ///     - Composite types where we simply call the correct function
-fn handle_simple_write(
+pub(super) fn handle_simple_write(
     id: &proc_macro2::TokenStream,
     data_type: &syn::Type,
     endianness: Endianness,
+    meta: &Meta,
 ) -> proc_macro2::TokenStream {
-    if RUST_TYPES.contains(&&*data_type.to_token_stream().to_string()) {
+    if let syn::Type::Tuple(tuple) = data_type {
+        // write each element in turn, stopping at the first error
+        let writes = tuple.elems.iter().enumerate().map(|(index, elem)| {
+            let index = syn::Index::from(index);
+            handle_simple_write(&quote! { #id.#index }, elem, endianness, meta)
+        });
+
+        quote! { (|| { #(#writes?;)* ::std::io::Result::Ok(()) })() }
+    } else if let syn::Type::Array(array) = data_type {
+        // elements are only borrowed, as composite types aren't `Copy`
+        let write = handle_simple_write(&quote! { (*element) }, &array.elem, endianness, meta);
+
+        quote! { #id.iter().try_for_each(|element| #write) }
+    } else if RUST_TYPES.contains(&&*data_type.to_token_stream().to_string()) {
         // simple case where writer code exists, can just writer::write_<type>();
 
         let fn_call = format_ident!("write_{}", data_type.to_token_stream().to_string());
 
+        // single bytes have no endianness
+        if matches!(&*data_type.to_token_stream().to_string(), "u8" | "i8") {
+            return quote! { writer.#fn_call(#id) };
+        }
+
         match endianness {
             Endianness::Little => {
                 quote! {  writer.#fn_call::<::byteorder::LittleEndian>(#id) }
@@ -34,30 +71,44 @@ fn handle_simple_write(
         // matches boolean logic in original savecodec2
 
         quote! { writer.write_u8(if #id { 1 } else { 0 }) }
+    } else if data_type.to_token_stream().to_string() == "char" {
+        // only characters read from a single byte can be written back as one
+        quote! {
+            u8::try_from(#id)
+                .map_err(|error| ::std::io::Error::new(::std::io::ErrorKind::InvalidInput, error))
+                .and_then(|byte| writer.write_u8(byte))
+        }
     } else {
-        quote! { #id.write(writer) }
+        let write_fn = &meta.write_fn;
+        quote! { #id.#write_fn(writer) }
     }
 }
 
 /// Generates a conditioanl write
 pub(super) fn generate_conditional_write(
     condition: &Condition,
-    id: &syn::Ident,
+    item: &Item,
     statement: proc_macro2::TokenStream,
-    data_type: &syn::Type,
 ) -> proc_macro2::TokenStream {
+    let Item { id, data_type, .. } = item;
+    let field = field_name(item);
+
     // advance pointer if needed, otherwies just return okay
     if condition.advance_if_false {
+        // zero fill exactly as many bytes as the read skips
+        let size = encoded_size(data_type).unwrap_or_else(|| {
+            abort!(data_type, "advance_if_false needs a type with a fixed size")
+        });
         quote! {
-            if let Some(#id) = self.#id {
+            if let Some(#id) = &self.#field {
                 #statement
             } else {
-                writer.write_all(&[0u8; std::mem::size_of::<#data_type>()])
+                writer.write_all(&[0u8; #size])
             }?
         }
     } else {
         quote! {
-            if let Some(#id) = self.#id {
+            if let Some(#id) = &self.#field {
                 #statement?
             }
         }
@@ -67,40 +118,122 @@ pub(super) fn generate_conditional_write(
 /// Generates a vector of statements that write the correct type to a writer.
 pub(super) fn generate_write_calls(
     items: &[Item],
-    endianness: Endianness,
+    format: &Format,
     struct_name: &syn::Ident,
 ) -> Vec<proc_macro2::TokenStream> {
-    /// Checks if type contains any symbols which indicate if it's a complex type (like `Option<T>`)
-    #[inline(always)]
-    fn is_simple_type(path: &syn::Path) -> bool {
-        path.segments
-            .first()
-            .map(|x| !x.ident.to_string().contains("<>"))
-            .unwrap_or(false)
-    }
+    let meta = &format.meta;
 
     items
         .iter()
-        .map(|item| {
+        .enumerate()
+        .map(|(index, item)| {
             let Item {
                 id,
                 data_type,
                 condition,
                 repetition,
+                ..
             } = item;
 
-            if let Type::Path(TypePath { path, .. }) = data_type && is_simple_type(path) {
-                let write =  if condition.is_some() || repetition.is_some() {
+            // column-wise layouts are written as a whole
+            if item.layout.is_some() {
+                let write = generate_soa_write(item, format, struct_name);
+                return quote! { #write? };
+            }
+
+            // bit fields are written together, once the last field of their unit is reached
+            if matches!(item.encoding, Some(Encoding::Bits(_))) {
+                return match generate_bits_write(item, items, meta) {
+                    Some(write) => quote! { #write? },
+                    None => quote! {},
+                };
+            }
+
+            // checksum fields are worked out from the bytes in their range
+            if matches!(item.encoding, Some(Encoding::Checksum(_))) {
+                let write = generate_checksum_field_write(item, meta);
+                return quote! { #write? };
+            }
+
+            // permuted fields are written as a whole, in their stored order
+            if item.permute_seed.is_some() {
+                let write = generate_permuted_write(item, items, format, struct_name);
+                return quote! { #write? };
+            }
+
+            // enciphered composites are serialized as a whole, and enciphered before they're written
+            if item.cipher_seed.is_some() {
+                let write = generate_enciphered_write(item, items, format, struct_name);
+                return quote! { #write? };
+            }
+
+            if is_supported_type(data_type) {
+                let value = if item.length_of.is_some() {
+                    // lengths are worked out from their target
+                    quote! { length }
+                } else if item.enum_type.is_some() {
+                    // enums write the index of their value in the table
+                    quote! { index as #data_type }
+                } else if (condition.is_some() || repetition.is_some()) && is_scalar(data_type) {
+                    // repeated and conditional values are borrowed, so scalars need dereferencing
+                    quote! { *#id }
+                } else if condition.is_some() || repetition.is_some() {
                     // if type has a condition or repetition, just pass the raw id and let the functions handle it
-                    handle_simple_write(&quote! { #id }, data_type, endianness)
+                    quote! { #id }
                 } else {
                     // otherwise need to pass self.id
-                    handle_simple_write(&quote! { self.#id }, data_type, endianness)
+                    let field = field_name(item);
+                    quote! { self.#field }
+                };
+                let write = match (&item.encoding, &item.big_endian_if) {
+                    (Some(encoding), _) => generate_encoded_write(&value, encoding, item, items, meta),
+                    (None, Some(expr)) => {
+                        // choose the endianness at runtime, with any fields the choice depends on in scope
+                        let bindings = bind_referenced_fields(expr, &items[..index]);
+                        let big = Endianness::Big.swapped_if(item.byteswap);
+                        let big = handle_simple_write(&value, data_type, big, meta);
+                        let little = Endianness::Little.swapped_if(item.byteswap);
+                        let little = handle_simple_write(&value, data_type, little, meta);
+                        quote! { { #bindings if #expr { #big } else { #little } } }
+                    }
+                    (None, None) => {
+                        let endianness = endianness_of(item, meta);
+                        let write = handle_simple_write(&value, data_type, endianness, meta);
+                        match &item.narrowing {
+                            Some(narrowing) => {
+                                // the flag may depend on earlier fields, so they need to be in scope
+                                let bindings =
+                                    bind_referenced_fields(&narrowing.expression, &items[..index]);
+                                let narrow_write = handle_simple_write(
+                                    &quote! { narrowed },
+                                    &narrowing.data_type,
+                                    endianness,
+                                    meta,
+                                );
+                                generate_narrowed_write(
+                                    narrowing,
+                                    id,
+                                    &value,
+                                    bindings,
+                                    narrow_write,
+                                    write,
+                                )
+                            }
+                            None => write,
+                        }
+                    }
                 };
-                let write = create_statement(write, id, data_type, condition, repetition, Method::Writing);
+                let write = generate_enum_write(item, items, write);
+                let write = generate_length_write(item, items, write);
+                let write = create_statement(write, item, meta, Method::Writing);
+                let write = generate_until_check(item, &items[..index], write);
+                let write = generate_conditional_repetition_check(item, &items[..index], write);
 
                 // conditional code has custom error handling, otherwise just standard error propagation
-                if condition.is_some() {
+                if matches!(repetition, Some(Repetition::Footer(_))) {
+                    // the length written is needed for the footer
+                    quote! { let _footer_length = #write? }
+                } else if condition.is_some() && repetition.is_none() {
                     quote! { #write }
                 } else {
                     quote! { #write? }
@@ -109,5 +242,7 @@ pub(super) fn generate_write_calls(
                 abort!(struct_name, "can only handle simple types (try removing any Options or Results in config file)")
             }
         })
+        .zip(items)
+        .map(|(write, item)| generate_positioned_write(item, write))
         .collect()
 }
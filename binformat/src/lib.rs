@@ -1,4 +1,3 @@
-#![feature(let_chains)]
 #![warn(clippy::cognitive_complexity)]
 
 mod generation;
@@ -9,33 +8,211 @@ use parse::Endianness;
 use proc_macro::TokenStream;
 use proc_macro_error::{abort, proc_macro_error};
 use serde_yaml::Value;
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
 use syn::{parse_macro_input, AttributeArgs, ItemStruct, Lit};
 
 #[derive(Debug, Clone)]
 enum Repetition {
     Count(syn::Expr),
+    /// Repeated until the byte length stored (as the given type) at the end of the data is used up
+    Footer(syn::Type),
+    /// Repeated the number of times stored (as the given type) just before the elements
+    Prefix(syn::Type),
+    /// Repeated until the end of the data
+    Eof,
+    /// Repeated until an element (available to the expression as `element`) matches the expression,
+    /// which is kept as the last element
+    Until(syn::Expr),
+}
+
+/// Patterns a string field can be matched against
+#[derive(Debug, Clone)]
+enum Pattern {
+    StartsWith(String),
+    Contains(String),
+}
+
+/// What decides whether a conditional item is present
+#[derive(Debug, Clone)]
+enum Predicate {
+    Expression(Box<syn::Expr>),
+    /// A previously read string field matching a pattern
+    Match {
+        field: Box<syn::Expr>,
+        pattern: Pattern,
+    },
+    /// There being data left to read, for trailing fields which older data may not have
+    DataRemaining,
 }
 
 #[derive(Debug, Clone)]
 struct Condition {
-    expression: syn::ExprBinary,
+    predicate: Predicate,
     advance_if_false: bool,
 }
 
+/// Encodings for types which aren't read directly with a single reader call
+#[derive(Debug, Clone)]
+enum Encoding {
+    /// String terminated by the given (non-empty) byte sequence
+    DelimitedString(Vec<u8>),
+    /// String whose length in bytes is given by an earlier integer field
+    LengthPrefixedString(syn::Ident),
+    /// One of several types, chosen by the value of an earlier integer field
+    TaggedUnion(Union),
+    /// Signed LEB128 integer, read into an `i64`
+    Varint,
+    /// Unsigned LEB128 integer, read into a `u64`
+    Varuint,
+    /// Value computed from earlier fields when reading, which takes no bytes
+    Computed(Box<syn::Expr>),
+    /// Some of the bits of an unsigned integer shared with neighbouring bit fields
+    Bits(BitField),
+    /// Checksum of a range of the data's bytes, which is checked when reading and worked out when writing
+    Checksum(ChecksumRange),
+}
+
+/// Bytes covered by a checksum field, as offsets from the start of the data. Without an end, the range
+/// covers everything up to the checksum.
+#[derive(Debug, Clone)]
+struct ChecksumRange {
+    checksum: Checksum,
+    start: usize,
+    end: Option<usize>,
+}
+
+/// A field packed into the next `width` bits of a unit, which holds consecutive bit fields up to a whole
+/// number of bytes
+#[derive(Debug, Clone)]
+struct BitField {
+    width: u32,
+    /// Local the whole unit is read into, named after the unit's first field
+    unit: syn::Ident,
+}
+
+/// An enum generated for a tagged union, with a variant holding each of its types
+#[derive(Debug, Clone)]
+struct Union {
+    name: syn::Ident,
+    tag: syn::Ident,
+    /// Value of the tag for each type, which is also the name of its variant
+    variants: Vec<(u64, syn::Ident)>,
+}
+
+/// A smaller integer type an item is stored as when an expression holds
+#[derive(Debug, Clone)]
+struct Narrowing {
+    data_type: syn::Type,
+    expression: syn::Expr,
+}
+
+/// Layouts for repeated composite types, other than storing each element in turn
+#[derive(Debug, Clone)]
+enum Layout {
+    /// Each field is stored for every element before moving on to the next field
+    StructOfArrays,
+}
+
 #[derive(Debug, Clone)]
 struct Item {
     id: syn::Ident,
     data_type: syn::Type,
     condition: Option<Condition>,
     repetition: Option<Repetition>,
+    reference: Option<syn::Ident>,
+    enum_type: Option<syn::Type>,
+    encoding: Option<Encoding>,
+    big_endian_if: Option<syn::Expr>,
+    /// Field whose value selects the byte order of this and later items
+    endian_of: Option<syn::Ident>,
+    byteswap: bool,
+    /// Byte order of this item, if it overrides the format's
+    endianness: Option<Endianness>,
+    layout: Option<Layout>,
+    narrowing: Option<Narrowing>,
+    length_of: Option<syn::Ident>,
+    pad_to: Option<usize>,
+    align: Option<usize>,
+    assert_len: Option<usize>,
+    /// Bytes of reserved space skipped before this item, from `skip` items in the format
+    skip_before: Option<usize>,
+    /// Bytes of reserved space skipped after this item, from `skip` items ending the format
+    skip_after: Option<usize>,
+    context: bool,
+    permute_seed: Option<syn::Ident>,
+    /// Field whose stored bytes are the key the whole composite is enciphered with
+    cipher_seed: Option<syn::Ident>,
+    /// Name of the generated struct field, if it differs from `id`
+    rename: Option<syn::Ident>,
 }
 
 #[derive(Debug)]
-struct Format {
+struct Conversion {
+    source: syn::Ident,
+    /// Where the source type's format was read from, so changes to it are tracked
+    path: PathBuf,
+    source_items: Vec<Item>,
+    defaults: HashMap<syn::Ident, syn::Expr>,
+}
+
+/// Checksums which can trail the root struct, or be stored in a field
+#[derive(Debug, Clone)]
+enum Checksum {
+    Crc32,
+}
+
+#[derive(Debug)]
+struct Meta {
     endianness: Endianness,
+    read_fn: syn::Ident,
+    write_fn: syn::Ident,
+    from: Option<Conversion>,
+    flatten_accessors: Vec<Vec<syn::Ident>>,
+    checksum: Option<Checksum>,
+    max_depth: Option<usize>,
+    version: Option<u16>,
+    debug_assert_roundtrip: bool,
+    /// Sample data a test is generated for, checking it's written back unchanged after being read
+    roundtrip_test: Option<PathBuf>,
+    repr_c: bool,
+    fixed_counts: bool,
+    serde: bool,
+    default: bool,
+    field_errors: bool,
+    /// Extra traits to derive on generated types, besides those always derived and `Default`
+    derives: Vec<syn::Ident>,
+    getters: bool,
+    /// Takes a version as an argument to `read`, which expressions can use as `_version`
+    version_param: bool,
+    /// Compares floats by their bits in generated `PartialEq` impls, so NaN values read from the same bytes are equal
+    bitwise_eq: bool,
+    /// Bytes the root struct has to start with
+    magic: Option<Vec<u8>>,
+    /// Named values which can be used in place of literals in expressions
+    constants: HashMap<syn::Ident, syn::Expr>,
+    /// Names which can be used in place of a type, standing for the type they're given
+    aliases: HashMap<syn::Ident, syn::Type>,
+}
+
+#[derive(Debug)]
+struct Format {
+    meta: Meta,
     types: HashMap<syn::Ident, Vec<Item>>,
     items: Vec<Item>,
+    /// Files included for their types, so changes to them are tracked
+    includes: Vec<PathBuf>,
+}
+
+/// Resolves a format path relative to the crate using the macro, rather than wherever the compiler
+/// was run from. Absolute paths are left as they are.
+fn resolve_path(path: &str) -> PathBuf {
+    match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(manifest_dir) => Path::new(&manifest_dir).join(path),
+        None => PathBuf::from(path),
+    }
 }
 
 #[proc_macro_attribute]
@@ -53,13 +230,19 @@ pub fn format_source(attr: TokenStream, item: TokenStream) -> TokenStream {
         )
     };
 
-    let file_contents = std::fs::read_to_string(path)
+    let path = resolve_path(&path);
+    let file_contents = std::fs::read_to_string(&path)
         .unwrap_or_else(|_| abort!(item.attrs.first(), "Path provided is not a valid file."));
     let file: BTreeMap<String, Value> = serde_yaml::from_str(&file_contents)
         .unwrap_or_else(|_| abort!(item.attrs.first(), "Path provided is not valid yaml."));
 
-    let format = parse_file(file)
-        .unwrap_or_else(|| abort!(item.attrs.first(), "File provided is not a valid format."));
+    let format = parse_file(file).unwrap_or_else(|error| {
+        abort!(
+            item.attrs.first(),
+            "File provided is not a valid format: {}.",
+            error
+        )
+    });
 
-    generation::generate(item, format)
+    generation::generate(item, format, &path)
 }
@@ -1,6 +1,14 @@
-use crate::{Condition, Format, Item, Repetition};
+use crate::{
+    resolve_path, BitField, Checksum, ChecksumRange, Condition, Conversion, Encoding, Format, Item,
+    Layout, Meta, Narrowing, Pattern, Predicate, Repetition, Union,
+};
+use itertools::Itertools;
+use proc_macro_error::abort_call_site;
 use serde_yaml::{Mapping, Value};
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub(super) enum Endianness {
@@ -8,96 +16,744 @@ pub(super) enum Endianness {
     Big,
 }
 
-/// Parses the meta entry to find the endianness, defaulting to little endian
-fn parse_meta(meta: Option<&Value>) -> Endianness {
-    let is_be = meta
-        .and_then(|val| val.get("endian"))
-        .map_or(false, |endianness| endianness.as_str() == Some("be"));
+impl Endianness {
+    /// Gives the opposite endianness if `swap` is set, which is the same as swapping the bytes of each value
+    pub(super) fn swapped_if(self, swap: bool) -> Self {
+        match (self, swap) {
+            (Endianness::Little, true) => Endianness::Big,
+            (Endianness::Big, true) => Endianness::Little,
+            (endianness, false) => endianness,
+        }
+    }
+}
 
-    if is_be {
-        Endianness::Big
-    } else {
-        Endianness::Little
+/// Parses an endianness given either abbreviated (`le`/`be`) or in full (`little`/`big`)
+fn parse_endianness(value: &str) -> Option<Endianness> {
+    match value {
+        "le" | "little" => Some(Endianness::Little),
+        "be" | "big" => Some(Endianness::Big),
+        _ => None,
     }
 }
 
-fn parse_repetition(value: &str) -> Option<Repetition> {
-    let mut chars = value.chars();
+/// Traits which generated types always derive
+const ALWAYS_DERIVED: &[&str] = &["Debug", "Clone", "PartialEq"];
+
+/// Traits which can be derived on generated types by listing them in `derives`
+const EXTRA_DERIVES: &[&str] = &["Default", "Eq", "Hash", "PartialOrd", "Ord", "Copy"];
+
+/// Parses a list of traits to derive, giving the ones which aren't already derived. `Default` can't always
+/// be derived, so is instead handled the same as `default: true`.
+fn parse_derives(names: &Value, default: &mut bool) -> Option<Vec<syn::Ident>> {
+    let mut derives = Vec::new();
+    for name in names.as_sequence()? {
+        match name.as_str()? {
+            name if ALWAYS_DERIVED.contains(&name) => {}
+            "Default" => *default = true,
+            name if EXTRA_DERIVES.contains(&name) => derives.push(syn::parse_str(name).ok()?),
+            // unknown derives are rejected here, rather than giving a confusing error in the generated code
+            name => abort_call_site!(
+                "unknown derive `{}`, expected one of {}",
+                name,
+                ALWAYS_DERIVED.iter().chain(EXTRA_DERIVES).join(", ")
+            ),
+        }
+    }
+
+    Some(derives)
+}
+
+/// Parses named constants, whose values can be numbers or booleans
+fn parse_constants(constants: &Value) -> Option<HashMap<syn::Ident, syn::Expr>> {
+    constants
+        .as_mapping()?
+        .iter()
+        .map(|(name, value)| {
+            let name = syn::parse_str(name.as_str()?).ok()?;
+            let value = match value {
+                Value::Number(number) => syn::parse_str(&number.to_string()).ok()?,
+                Value::Bool(value) => syn::parse_str(&value.to_string()).ok()?,
+                _ => return None,
+            };
+
+            Some((name, value))
+        })
+        .collect()
+}
+
+/// Parses type aliases, each naming the type it stands for
+fn parse_aliases(aliases: &Value) -> Option<HashMap<syn::Ident, syn::Type>> {
+    aliases
+        .as_mapping()?
+        .iter()
+        .map(|(name, data_type)| {
+            Some((
+                syn::parse_str(name.as_str()?).ok()?,
+                syn::parse_str(data_type.as_str()?).ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Parses the meta entry, defaulting to little endian and `read`/`write` method names
+fn parse_meta(meta: Option<&Value>) -> Option<Meta> {
+    // an unknown endianness is almost certainly a typo, so is rejected rather than silently defaulting
+    let endianness = match meta.and_then(|val| val.get("endian")) {
+        Some(endianness) => endianness
+            .as_str()
+            .and_then(parse_endianness)
+            .unwrap_or_else(|| {
+                abort_call_site!("endian must be one of `le`, `be`, `little` or `big`")
+            }),
+        None => Endianness::Little,
+    };
+
+    // method names have to be valid identifiers if they're overridden
+    let method_name = |key: &str, default: &str| match meta.and_then(|val| val.get(key)) {
+        Some(name) => syn::parse_str(name.as_str()?).ok(),
+        None => Some(syn::Ident::new(default, proc_macro2::Span::call_site())),
+    };
+    let read_fn = method_name("read_fn", "read")?;
+    let write_fn = method_name("write_fn", "write")?;
+
+    // options which can be turned on are off by default
+    let flag = |key: &str| match meta.and_then(|val| val.get(key)) {
+        Some(enabled) => enabled.as_bool(),
+        None => Some(false),
+    };
+
+    let from = match meta.and_then(|val| val.get("from")) {
+        Some(from) => Some(parse_conversion(from)?),
+        None => None,
+    };
+
+    // paths are given like `header.flags`
+    let flatten_accessors = match meta.and_then(|val| val.get("flatten_accessors")) {
+        Some(paths) => paths
+            .as_sequence()?
+            .iter()
+            .map(|path| {
+                path.as_str()?
+                    .split('.')
+                    .map(|segment| syn::parse_str(segment).ok())
+                    .collect()
+            })
+            .collect::<Option<_>>()?,
+        None => Vec::new(),
+    };
+
+    let checksum = match meta.and_then(|val| val.get("crc")) {
+        Some(checksum) => match checksum.as_str()? {
+            "crc32" => Some(Checksum::Crc32),
+            _ => return None,
+        },
+        None => None,
+    };
+
+    let max_depth = match meta.and_then(|val| val.get("max_depth")) {
+        Some(depth) => Some(depth.as_u64()?.try_into().ok()?),
+        None => None,
+    };
 
-    let discriminant = chars.by_ref().take_while(|&c| c != '(').collect::<String>();
-    let expression = chars.by_ref().take_while(|&c| c != ')').collect::<String>();
+    let version = match meta.and_then(|val| val.get("version")) {
+        Some(version) => Some(version.as_u64()?.try_into().ok()?),
+        None => None,
+    };
 
-    match &discriminant[..] {
-        "Count" => Some(Repetition::Count(syn::parse_str(&expression).ok()?)),
+    let debug_assert_roundtrip = flag("debug_assert_roundtrip")?;
+    let roundtrip_test = match meta.and_then(|val| val.get("roundtrip_test")) {
+        Some(sample) => Some(resolve_path(sample.as_str()?)),
+        None => None,
+    };
+    let repr_c = flag("repr_c")?;
+    let fixed_counts = flag("fixed_counts")?;
+    let serde = flag("serde")?;
+    let mut default = flag("default")?;
+    let getters = flag("getters")?;
+    let bitwise_eq = flag("bitwise_eq")?;
+    let version_param = flag("version_param")?;
+
+    let derives = match meta.and_then(|val| val.get("derives")) {
+        Some(names) => parse_derives(names, &mut default)?,
+        None => Vec::new(),
+    };
+
+    let magic = match meta.and_then(|val| val.get("magic")) {
+        Some(magic) => Some(parse_bytes(magic)?),
+        None => None,
+    };
+
+    let field_errors = flag("field_errors")?;
+
+    let constants = match meta.and_then(|val| val.get("constants")) {
+        Some(constants) => parse_constants(constants)?,
+        None => HashMap::new(),
+    };
+
+    let aliases = match meta.and_then(|val| val.get("aliases")) {
+        Some(aliases) => parse_aliases(aliases)?,
+        None => HashMap::new(),
+    };
+
+    Some(Meta {
+        endianness,
+        read_fn,
+        write_fn,
+        from,
+        flatten_accessors,
+        checksum,
+        max_depth,
+        version,
+        debug_assert_roundtrip,
+        roundtrip_test,
+        repr_c,
+        fixed_counts,
+        serde,
+        default,
+        field_errors,
+        derives,
+        getters,
+        bitwise_eq,
+        version_param,
+        magic,
+        constants,
+        aliases,
+    })
+}
+
+/// Parses a conversion from another format, reading that format's file to find its fields
+fn parse_conversion(from: &Value) -> Option<Conversion> {
+    let source = syn::parse_str(from.get("type")?.as_str()?).ok()?;
+
+    let path = resolve_path(from.get("format")?.as_str()?);
+    let contents = std::fs::read_to_string(&path).ok()?;
+    let file: BTreeMap<String, Value> = serde_yaml::from_str(&contents).ok()?;
+    let source_items = parse_sequence(file.get("items")).ok()?;
+
+    // defaults can be any yaml scalar, which are then parsed as rust expressions
+    let defaults = match from.get("defaults") {
+        Some(defaults) => defaults
+            .as_mapping()?
+            .iter()
+            .map(|(id, value)| {
+                let id = syn::parse_str(id.as_str()?).ok()?;
+                let value = match value {
+                    Value::String(value) => value.clone(),
+                    Value::Number(value) => value.to_string(),
+                    Value::Bool(value) => value.to_string(),
+                    _ => return None,
+                };
+
+                Some((id, syn::parse_str(&value).ok()?))
+            })
+            .collect::<Option<_>>()?,
+        None => HashMap::new(),
+    };
+
+    Some(Conversion {
+        source,
+        path,
+        source_items,
+        defaults,
+    })
+}
+
+fn parse_repetition(value: &str) -> Option<Repetition> {
+    // expressions can contain brackets themselves, so only the outermost pair is removed
+    let (discriminant, expression) = match value.split_once('(') {
+        Some((discriminant, rest)) => (discriminant, rest.strip_suffix(')')?),
+        None => (value, ""),
+    };
+
+    match discriminant {
+        "Count" => Some(Repetition::Count(syn::parse_str(expression).ok()?)),
+        "Footer" => Some(Repetition::Footer(syn::parse_str(expression).ok()?)),
+        "Until" => Some(Repetition::Until(syn::parse_str(expression).ok()?)),
+        "Eof" if expression.is_empty() => Some(Repetition::Eof),
         _ => None,
     }
 }
 
-/// Parse an individual item
-fn parse_item(item: &Mapping) -> Option<Item> {
-    let id = syn::parse_str(item.get("id")?.as_str()?).ok()?;
-    let data_type = syn::parse_str(item.get("type")?.as_str()?).ok()?;
-    let condition_expr = item
-        .get("if")
-        .and_then(Value::as_str)
-        .and_then(|cond| syn::parse_str(cond).ok());
-    let repetition = item
-        .get("repeat")
-        .and_then(Value::as_str)
-        .and_then(parse_repetition);
-    let advance_if_false = item
-        .get("advance_if_false")
-        .and_then(Value::as_bool)
-        .unwrap_or(false);
-
-    let condition = condition_expr.map(|expression| Condition {
-        expression,
+/// Parses a non-empty string of bytes like a terminator, given either as a string or a sequence of bytes
+fn parse_bytes(value: &Value) -> Option<Vec<u8>> {
+    let bytes: Vec<u8> = match value {
+        Value::String(string) => string.as_bytes().to_vec(),
+        Value::Sequence(bytes) => bytes
+            .iter()
+            .map(|byte| byte.as_u64()?.try_into().ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+
+    (!bytes.is_empty()).then_some(bytes)
+}
+
+/// Parses the type of an item, along with an encoding for types which need one
+fn parse_type(item: &Mapping) -> Option<(syn::Type, Option<Encoding>)> {
+    let data_type = item.get("type")?.as_str()?;
+
+    match data_type {
+        "delimited_string" => Some((
+            syn::parse_str("String").ok()?,
+            Some(Encoding::DelimitedString(parse_bytes(
+                item.get("terminator")?,
+            )?)),
+        )),
+        // C strings are delimited strings ending in a zero byte
+        "cstring" => Some((
+            syn::parse_str("String").ok()?,
+            Some(Encoding::DelimitedString(vec![0])),
+        )),
+        "string" => Some((
+            syn::parse_str("String").ok()?,
+            Some(Encoding::LengthPrefixedString(
+                syn::parse_str(item.get("len")?.as_str()?).ok()?,
+            )),
+        )),
+        "varint" => Some((syn::parse_str("i64").ok()?, Some(Encoding::Varint))),
+        "varuint" => Some((syn::parse_str("u64").ok()?, Some(Encoding::Varuint))),
+        "bits" => Some(parse_bits(item)?),
+        "checksum" => Some((
+            syn::parse_str("u32").ok()?,
+            Some(Encoding::Checksum(parse_checksum_range(item)?)),
+        )),
+        "computed" => Some((
+            syn::parse_str(item.get("value_type")?.as_str()?).ok()?,
+            Some(Encoding::Computed(Box::new(
+                syn::parse_str(item.get("expr")?.as_str()?).ok()?,
+            ))),
+        )),
+        "enum" => {
+            let union = parse_union(item)?;
+            Some((
+                syn::parse_str(&union.name.to_string()).ok()?,
+                Some(Encoding::TaggedUnion(union)),
+            ))
+        }
+        _ => Some((syn::parse_str(data_type).ok()?, None)),
+    }
+}
+
+/// Parses the algorithm and range of a checksum field. The range is given like a rust range of byte
+/// offsets, such as `4..20` or `4..`, and covers everything before the checksum if it's left out.
+fn parse_checksum_range(item: &Mapping) -> Option<ChecksumRange> {
+    let checksum = match item.get("algo")?.as_str()? {
+        "crc32" => Checksum::Crc32,
+        _ => return None,
+    };
+
+    let (start, end) = match item.get("range") {
+        Some(range) => range.as_str()?.split_once("..")?,
+        None => ("", ""),
+    };
+    let start = match start.trim() {
+        "" => 0,
+        start => start.parse().ok()?,
+    };
+    let end = match end.trim() {
+        "" => None,
+        end => Some(end.parse().ok()?),
+    };
+
+    Some(ChecksumRange {
+        checksum,
+        start,
+        end,
+    })
+}
+
+/// Parses a bit field, stored as the smallest type which holds its width - or a `bool` for single bits.
+/// Every field starts out in its own unit, until joined with its neighbours by `parse_sequence`.
+fn parse_bits(item: &Mapping) -> Option<(syn::Type, Option<Encoding>)> {
+    let width: u32 = item.get("width")?.as_u64()?.try_into().ok()?;
+    let data_type = match width {
+        1 => "bool",
+        2..=8 => "u8",
+        9..=16 => "u16",
+        17..=32 => "u32",
+        33..=64 => "u64",
+        _ => return None,
+    };
+    let unit = syn::parse_str(&format!("_bits_{}", item.get("id")?.as_str()?)).ok()?;
+
+    Some((
+        syn::parse_str(data_type).ok()?,
+        Some(Encoding::Bits(BitField { width, unit })),
+    ))
+}
+
+/// Parses a tagged union, given as the name of its enum, the field holding its tag, and a mapping of tag
+/// values to types
+fn parse_union(item: &Mapping) -> Option<Union> {
+    let name = syn::parse_str(item.get("name")?.as_str()?).ok()?;
+    let tag = syn::parse_str(item.get("tag")?.as_str()?).ok()?;
+    let variants: Vec<_> = item
+        .get("variants")?
+        .as_mapping()?
+        .iter()
+        .map(|(value, variant)| Some((value.as_u64()?, syn::parse_str(variant.as_str()?).ok()?)))
+        .collect::<Option<_>>()?;
+
+    (!variants.is_empty()).then_some(Union {
+        name,
+        tag,
+        variants,
+    })
+}
+
+/// Parses a pattern match on a string field, given as the field and one of `starts_with` or `contains`
+fn parse_match(value: &Value) -> Option<Predicate> {
+    let field = syn::parse_str(value.get("field")?.as_str()?).ok()?;
+    let pattern = match (value.get("starts_with"), value.get("contains")) {
+        (Some(prefix), None) => Pattern::StartsWith(prefix.as_str()?.to_owned()),
+        (None, Some(substring)) => Pattern::Contains(substring.as_str()?.to_owned()),
+        _ => return None,
+    };
+
+    Some(Predicate::Match { field, pattern })
+}
+
+/// Parses an optional number of bytes, which has to be positive
+fn parse_size(value: Option<&Value>) -> Option<Option<usize>> {
+    match value {
+        Some(size) => Some(Some(
+            size.as_u64()?
+                .try_into()
+                .ok()
+                .filter(|&size: &usize| size > 0)?,
+        )),
+        None => Some(None),
+    }
+}
+
+/// Parses an optional string, which is fine to leave out but has to be valid rust syntax if it's given
+fn parse_optional<T: syn::parse::Parse>(value: Option<&Value>) -> Option<Option<T>> {
+    match value {
+        Some(value) => Some(Some(syn::parse_str(value.as_str()?).ok()?)),
+        None => Some(None),
+    }
+}
+
+/// Parse an individual item, giving an error naming the item and the key which is invalid
+fn parse_item(item: &Mapping) -> Result<Item, String> {
+    let id: syn::Ident = match item.get("id") {
+        Some(id) => id
+            .as_str()
+            .and_then(|id| syn::parse_str(id).ok())
+            .ok_or_else(|| format!("invalid id `{}`", describe(id)))?,
+        None => return Err("item is missing an `id`".to_owned()),
+    };
+    let invalid = |key: &str| match item.get(key) {
+        Some(value) => format!("item `{}` has invalid {} `{}`", id, key, describe(value)),
+        None => format!("item `{}` is missing `{}`", id, key),
+    };
+
+    let flag = |key: &str| match item.get(key) {
+        Some(value) => value.as_bool().ok_or_else(|| invalid(key)),
+        None => Ok(false),
+    };
+
+    let (data_type, encoding) = parse_type(item).ok_or_else(|| invalid("type"))?;
+    let predicate = match (item.get("if"), item.get("if_match")) {
+        (None, None) => None,
+        (Some(_), None) => parse_optional(item.get("if"))
+            .ok_or_else(|| invalid("if"))?
+            .map(|expr| Predicate::Expression(Box::new(expr))),
+        (None, Some(pattern)) => Some(parse_match(pattern).ok_or_else(|| invalid("if_match"))?),
+        (Some(_), Some(_)) => {
+            return Err(format!("item `{}` can't have both `if` and `if_match`", id))
+        }
+    };
+    // fields missing from the end of older data are conditional on there being any left
+    let predicate = match (predicate, flag("optional_eof")?) {
+        (predicate, false) => predicate,
+        (None, true) => Some(Predicate::DataRemaining),
+        (Some(_), true) => {
+            return Err(format!(
+                "item `{}` can't have both a condition and `optional_eof`",
+                id
+            ))
+        }
+    };
+    let repetition = match (item.get("repeat"), item.get("len_prefix")) {
+        (None, None) => None,
+        (Some(repeat), None) => Some(
+            repeat
+                .as_str()
+                .and_then(parse_repetition)
+                .ok_or_else(|| invalid("repeat"))?,
+        ),
+        (None, Some(_)) => parse_optional(item.get("len_prefix"))
+            .ok_or_else(|| invalid("len_prefix"))?
+            .map(Repetition::Prefix),
+        (Some(_), Some(_)) => {
+            return Err(format!(
+                "item `{}` can't have both `repeat` and `len_prefix`",
+                id
+            ))
+        }
+    };
+    let reference = parse_optional(item.get("ref")).ok_or_else(|| invalid("ref"))?;
+    let enum_type = parse_optional(item.get("enum")).ok_or_else(|| invalid("enum"))?;
+    let big_endian_if =
+        parse_optional(item.get("big_endian_if")).ok_or_else(|| invalid("big_endian_if"))?;
+    let endian_of = parse_optional(item.get("endian_of")).ok_or_else(|| invalid("endian_of"))?;
+    let byteswap = flag("byteswap")?;
+    let endianness = match item.get("endian") {
+        Some(endianness) => Some(
+            endianness
+                .as_str()
+                .and_then(parse_endianness)
+                .ok_or_else(|| invalid("endian"))?,
+        ),
+        None => None,
+    };
+    let length_of = parse_optional(item.get("length_of")).ok_or_else(|| invalid("length_of"))?;
+    let pad_to = parse_size(item.get("pad_to")).ok_or_else(|| invalid("pad_to"))?;
+    let align = parse_size(item.get("align")).ok_or_else(|| invalid("align"))?;
+    let assert_len = parse_size(item.get("assert_len")).ok_or_else(|| invalid("assert_len"))?;
+    let context = flag("context")?;
+    let permute_seed =
+        parse_optional(item.get("permute_seed")).ok_or_else(|| invalid("permute_seed"))?;
+    let cipher_seed =
+        parse_optional(item.get("cipher_seed")).ok_or_else(|| invalid("cipher_seed"))?;
+    let rename = parse_optional(item.get("rename")).ok_or_else(|| invalid("rename"))?;
+    let advance_if_false = flag("advance_if_false")?;
+
+    let layout = match item.get("layout").map(Value::as_str) {
+        None => None,
+        Some(Some("soa")) => Some(Layout::StructOfArrays),
+        Some(_) => return Err(invalid("layout")),
+    };
+
+    let narrowing = match (
+        parse_optional(item.get("narrow_type")).ok_or_else(|| invalid("narrow_type"))?,
+        parse_optional(item.get("narrow_if")).ok_or_else(|| invalid("narrow_if"))?,
+    ) {
+        (None, None) => None,
+        (Some(data_type), Some(expression)) => Some(Narrowing {
+            data_type,
+            expression,
+        }),
+        _ => {
+            return Err(format!(
+                "item `{}` needs both `narrow_type` and `narrow_if`",
+                id
+            ))
+        }
+    };
+
+    let condition = predicate.map(|predicate| Condition {
+        predicate,
         advance_if_false,
     });
 
-    Some(Item {
+    Ok(Item {
         id,
         data_type,
         condition,
         repetition,
+        reference,
+        enum_type,
+        encoding,
+        big_endian_if,
+        endian_of,
+        byteswap,
+        endianness,
+        layout,
+        narrowing,
+        length_of,
+        pad_to,
+        align,
+        assert_len,
+        skip_before: None,
+        skip_after: None,
+        context,
+        permute_seed,
+        cipher_seed,
+        rename,
     })
 }
 
-/// Parse a sequence of values
-fn parse_sequence(item: Option<&Value>) -> Vec<Item> {
-    item.and_then(|val| val.as_sequence())
-        .map_or_else(Vec::new, |val| {
-            val.iter()
-                .filter_map(|value| value.as_mapping().and_then(parse_item))
-                .collect()
-        })
+/// Describes a yaml value for an error message, as it would be written in the format
+fn describe(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => serde_yaml::to_string(value)
+            .map(|value| value.trim_end().to_owned())
+            .unwrap_or_default(),
+    }
 }
 
-/// Parse the user-defined types
-fn parse_defined_types(item: Option<&Value>) -> HashMap<syn::Ident, Vec<Item>> {
-    fn parse_defined_type((name, items): (&Value, &Value)) -> Option<(syn::Ident, Vec<Item>)> {
-        let type_name = syn::parse_str(name.as_str()?).ok()?;
-        let items = parse_sequence(Some(items));
+/// Parses the size of a `skip` item, which reserves space without generating a field, or `None` for
+/// any other item
+fn parse_skip(item: &Mapping) -> Option<Result<usize, String>> {
+    if item.get("type")?.as_str()? != "skip" {
+        return None;
+    }
+
+    Some(
+        parse_size(item.get("size"))
+            .flatten()
+            .ok_or_else(|| "`skip` needs a positive `size`".to_owned()),
+    )
+}
 
-        Some((type_name, items))
+/// Error for a unit of bit fields which doesn't fill a whole number of bytes
+fn unfilled_unit(unit: &syn::Ident) -> String {
+    let first = unit.to_string();
+    format!(
+        "bit fields from `{}` don't fill a whole number of bytes",
+        first.trim_start_matches("_bits_")
+    )
+}
+
+/// Packs a bit field into the unit still being filled, if there is one, giving the unit once the item
+/// is added - or `None` once it's a whole number of bytes.
+fn join_bits(
+    item: &mut Item,
+    open: Option<(syn::Ident, u32)>,
+) -> Result<Option<(syn::Ident, u32)>, String> {
+    let Some(Encoding::Bits(field)) = &mut item.encoding else {
+        return match open {
+            Some((unit, _)) => Err(unfilled_unit(&unit)),
+            None => Ok(None),
+        };
+    };
+
+    let (unit, filled) = match open {
+        Some((unit, filled)) => {
+            if item.skip_before.is_some() {
+                return Err(format!(
+                    "can't skip bytes part way through a unit of bit fields (before `{}`)",
+                    item.id
+                ));
+            }
+            field.unit = unit.clone();
+            (unit, filled + field.width)
+        }
+        None => (field.unit.clone(), field.width),
+    };
+
+    match filled {
+        65.. => Err(format!(
+            "bit fields up to `{}` take more than 64 bits",
+            item.id
+        )),
+        filled if filled % 8 == 0 => Ok(None),
+        filled => Ok(Some((unit, filled))),
     }
+}
+
+/// Parse a sequence of values, with the space reserved by `skip` items given to the item after them
+/// (or the last item, for space at the end)
+fn parse_sequence(item: Option<&Value>) -> Result<Vec<Item>, String> {
+    let Some(values) = item.and_then(|val| val.as_sequence()) else {
+        return Ok(Vec::new());
+    };
 
-    item.and_then(|val| val.as_mapping())
-        .map_or_else(HashMap::new, |val| {
-            val.iter().filter_map(parse_defined_type).collect()
+    let mut items: Vec<Item> = Vec::new();
+    let mut skipped = None;
+    // the unit bit fields are being packed into, and how many bits it has so far
+    let mut bits: Option<(syn::Ident, u32)> = None;
+    for value in values.iter().filter_map(Value::as_mapping) {
+        if let Some(size) = parse_skip(value) {
+            skipped = Some(skipped.unwrap_or(0) + size?);
+        } else {
+            let mut item = parse_item(value)?;
+            item.skip_before = skipped.take();
+            bits = join_bits(&mut item, bits.take())?;
+            items.push(item);
+        }
+    }
+    if let Some((unit, _)) = bits {
+        return Err(unfilled_unit(&unit));
+    }
+    if let Some(last) = items.last_mut() {
+        last.skip_after = skipped;
+    }
+
+    Ok(items)
+}
+
+/// Parse the user-defined types
+fn parse_defined_types(item: Option<&Value>) -> Result<HashMap<syn::Ident, Vec<Item>>, String> {
+    let Some(types) = item.and_then(|val| val.as_mapping()) else {
+        return Ok(HashMap::new());
+    };
+
+    types
+        .iter()
+        .map(|(name, items)| {
+            let type_name = name
+                .as_str()
+                .and_then(|name| syn::parse_str(name).ok())
+                .ok_or_else(|| format!("invalid type name `{}`", describe(name)))?;
+            let items = parse_sequence(Some(items))
+                .map_err(|error| format!("{} (in type `{}`)", error, type_name))?;
+
+            Ok((type_name, items))
         })
+        .collect()
 }
 
-/// Parse the entire file, returning a format if it is valid
-pub(super) fn parse_file(items: BTreeMap<String, Value>) -> Option<Format> {
-    let endianness = parse_meta(items.get("meta"));
-    let types = parse_defined_types(items.get("types"));
-    let items = parse_sequence(items.get("items"));
+/// Parses the types of included files, and the files they include in turn. Types defined by a file take
+/// precedence over ones it includes, and later includes over earlier ones.
+fn parse_includes(
+    include: Option<&Value>,
+    including: &mut Vec<PathBuf>,
+    includes: &mut Vec<PathBuf>,
+) -> Result<HashMap<syn::Ident, Vec<Item>>, String> {
+    let Some(include) = include else {
+        return Ok(HashMap::new());
+    };
+    let paths = include
+        .as_sequence()
+        .ok_or_else(|| format!("invalid include `{}`", describe(include)))?;
 
-    Some(Format {
-        endianness,
+    let mut types = HashMap::new();
+    for path in paths {
+        let path = path
+            .as_str()
+            .map(resolve_path)
+            .ok_or_else(|| format!("invalid include `{}`", describe(path)))?;
+        if including.contains(&path) {
+            return Err(format!("`{}` is included in a cycle", path.display()));
+        }
+
+        let file: BTreeMap<String, Value> = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_yaml::from_str(&contents).ok())
+            .ok_or_else(|| format!("`{}` is not a valid file to include", path.display()))?;
+
+        including.push(path.clone());
+        let mut included = parse_includes(file.get("include"), including, includes)?;
+        including.pop();
+
+        included.extend(
+            parse_defined_types(file.get("types"))
+                .map_err(|error| format!("{} (in `{}`)", error, path.display()))?,
+        );
+        types.extend(included);
+        includes.push(path);
+    }
+
+    Ok(types)
+}
+
+/// Parse the entire file, returning a format if it is valid or a description of what isn't
+pub(super) fn parse_file(items: BTreeMap<String, Value>) -> Result<Format, String> {
+    let meta = parse_meta(items.get("meta")).ok_or("invalid `meta` section")?;
+    let mut includes = Vec::new();
+    let mut types = parse_includes(items.get("include"), &mut Vec::new(), &mut includes)?;
+    types.extend(parse_defined_types(items.get("types"))?);
+    let items = parse_sequence(items.get("items"))?;
+
+    Ok(Format {
+        meta,
         types,
         items,
+        includes,
     })
 }
 
@@ -108,7 +764,7 @@ mod tests {
 
     #[test]
     fn parse_meta_test() {
-        assert_eq!(parse_meta(None), Endianness::Little);
+        assert_eq!(parse_meta(None).unwrap().endianness, Endianness::Little);
 
         let le_value = {
             let mut le_value = Mapping::new();
@@ -118,7 +774,10 @@ mod tests {
             );
             Value::Mapping(le_value)
         };
-        assert_eq!(parse_meta(Some(&le_value)), Endianness::Little);
+        assert_eq!(
+            parse_meta(Some(&le_value)).unwrap().endianness,
+            Endianness::Little
+        );
 
         let be_value = {
             let mut be_value = Mapping::new();
@@ -128,16 +787,89 @@ mod tests {
             );
             Value::Mapping(be_value)
         };
-        assert_eq!(parse_meta(Some(&be_value)), Endianness::Big);
+        assert_eq!(
+            parse_meta(Some(&be_value)).unwrap().endianness,
+            Endianness::Big
+        );
 
-        let other_value = {
-            let mut other_value = Mapping::new();
-            other_value.insert(
+        let big_value = {
+            let mut big_value = Mapping::new();
+            big_value.insert(
                 Value::String("endian".to_owned()),
-                Value::String("other".to_owned()),
+                Value::String("big".to_owned()),
             );
-            Value::Mapping(other_value)
+            Value::Mapping(big_value)
         };
-        assert_eq!(parse_meta(Some(&other_value)), Endianness::Little);
+        assert_eq!(
+            parse_meta(Some(&big_value)).unwrap().endianness,
+            Endianness::Big
+        );
+    }
+
+    #[test]
+    fn parse_item_errors() {
+        let item = |yaml: &str| parse_item(&serde_yaml::from_str(yaml).unwrap());
+
+        assert_eq!(item("type: u8").unwrap_err(), "item is missing an `id`");
+        assert_eq!(
+            item("{ id: foo, type: '[u8; ]' }").unwrap_err(),
+            "item `foo` has invalid type `[u8; ]`"
+        );
+        assert_eq!(
+            item("{ id: foo, type: u8, repeat: Count(n }").unwrap_err(),
+            "item `foo` has invalid repeat `Count(n`"
+        );
+        assert_eq!(
+            item("{ id: foo, type: string }").unwrap_err(),
+            "item `foo` has invalid type `string`"
+        );
+        assert_eq!(
+            item("{ id: foo, type: u8, byteswap: maybe }").unwrap_err(),
+            "item `foo` has invalid byteswap `maybe`"
+        );
+        assert!(item("{ id: foo, type: u8, byteswap: true }").is_ok());
+    }
+
+    #[test]
+    fn parse_file_errors() {
+        let file = |yaml: &str| parse_file(serde_yaml::from_str(yaml).unwrap()).map(|_| ());
+
+        assert_eq!(
+            file("items: [{ id: a, type: u8 }, { id: b, type: 'Vec<' }]").unwrap_err(),
+            "item `b` has invalid type `Vec<`"
+        );
+        assert_eq!(
+            file("types: { Inner: [{ id: a, type: u8, endian: middle }] }").unwrap_err(),
+            "item `a` has invalid endian `middle` (in type `Inner`)"
+        );
+        assert_eq!(
+            file("items: [{ type: skip }]").unwrap_err(),
+            "`skip` needs a positive `size`"
+        );
+        assert_eq!(
+            file("items: [{ id: a, type: bits, width: 3 }, { id: b, type: u8 }]").unwrap_err(),
+            "bit fields from `a` don't fill a whole number of bytes"
+        );
+        assert_eq!(
+            file("items: [{ id: a, type: bits, width: 60 }, { id: b, type: bits, width: 12 }]")
+                .unwrap_err(),
+            "bit fields up to `b` take more than 64 bits"
+        );
+        assert!(file("include: [tests/formats/include_cycle.format]")
+            .unwrap_err()
+            .ends_with("include_cycle.format` is included in a cycle"));
+        assert!(file("include: [tests/formats/missing.format]")
+            .unwrap_err()
+            .ends_with("missing.format` is not a valid file to include"));
+    }
+
+    #[test]
+    fn parse_endianness_test() {
+        assert_eq!(parse_endianness("le"), Some(Endianness::Little));
+        assert_eq!(parse_endianness("little"), Some(Endianness::Little));
+        assert_eq!(parse_endianness("be"), Some(Endianness::Big));
+        assert_eq!(parse_endianness("big"), Some(Endianness::Big));
+        assert_eq!(parse_endianness("other"), None);
+        assert_eq!(parse_endianness("Big"), None);
     }
 }
@@ -0,0 +1,43 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/advance_if_false.format")]
+pub struct AdvanceIfFalse;
+
+/// Total encoded size of the fields which advance when absent
+const ADVANCED_SIZE: usize = 1 + 2 + 4 + 8 + 1 + 2 + 4 + 8 + 4 + 8 + 1 + 3;
+
+#[test]
+fn absent_advances_and_zero_fills() {
+    // absent fields with advance_if_false are skipped on read, and zero filled on write
+    let mut data = vec![0; 1 + ADVANCED_SIZE];
+    data.push(42);
+
+    let parsed = AdvanceIfFalse::read(&mut data.as_slice()).unwrap();
+    assert_eq!(parsed.advance_u64, None);
+    assert_eq!(parsed.advance_tuple, None);
+    assert_eq!(parsed.omit_u32, None);
+    assert_eq!(parsed.omit_tuple, None);
+    assert_eq!(parsed.end, 42);
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn present_reads_and_writes_values() {
+    // present fields take up the same space whether or not they advance when absent
+    let mut data = vec![1];
+    data.extend((1..=ADVANCED_SIZE as u8).map(|i| i % 2));
+    data.extend([1, 2, 3, 4, 5, 6, 7, 42]);
+
+    let parsed = AdvanceIfFalse::read(&mut data.as_slice()).unwrap();
+    assert_eq!(parsed.advance_tuple, Some((0, 1)));
+    assert_eq!(parsed.omit_u32, Some(0x04030201));
+    assert_eq!(parsed.omit_tuple, Some((5, 0x0706)));
+    assert_eq!(parsed.end, 42);
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
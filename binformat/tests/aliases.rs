@@ -0,0 +1,40 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/aliases.format")]
+pub struct Aliases;
+
+const DATA: [u8; 19] = [
+    0x01, 0x00, 0x00, 0x00, // balance
+    0x02, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, // history
+    0x04, 0x01, 0x00, 0x00, // price
+    0xff, 0x80, 0x00, // tint
+];
+
+#[test]
+fn aliases_are_read_as_their_type() {
+    let value = Aliases::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(value.balance, 1u32);
+    assert_eq!(value.history, [2u32, 3]);
+    assert_eq!(
+        value.item,
+        item {
+            price: 0x104,
+            tint: [0xff, 0x80, 0x00],
+        }
+    );
+}
+
+#[test]
+fn aliases_roundtrip() {
+    let value = Aliases::read(&mut &DATA[..]).unwrap();
+
+    let mut bytes = Vec::new();
+    value.write(&mut bytes).unwrap();
+    assert_eq!(bytes, DATA);
+}
+
+#[test]
+fn aliases_have_the_size_of_their_type() {
+    assert_eq!(Aliases::SIZE, DATA.len());
+}
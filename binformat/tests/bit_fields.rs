@@ -0,0 +1,73 @@
+use binformat::format_source;
+use std::io::ErrorKind;
+
+#[format_source("tests/formats/bit_fields.format")]
+pub struct BitFields;
+
+const DATA: [u8; 12] = [
+    3,           // version
+    0b1010_1101, // colour 43, locked 0, visible 1
+    0x12,
+    0x34, // y 0x91, x 0x14
+    1,
+    2,
+    3,
+    4,
+    5,
+    6,
+    7,
+    8, // tail
+];
+
+fn value() -> BitFields {
+    BitFields {
+        version: 3,
+        flags: flags {
+            visible: true,
+            locked: false,
+            colour: 43,
+        },
+        x: 0x14,
+        y: 0x91,
+        tail: 0x0102_0304_0506_0708,
+    }
+}
+
+#[test]
+fn bit_fields_read() {
+    assert_eq!(BitFields::read(&mut &DATA[..]).unwrap(), value());
+}
+
+#[test]
+fn bit_fields_write() {
+    let mut bytes = Vec::new();
+    value().write(&mut bytes).unwrap();
+    assert_eq!(bytes, DATA);
+}
+
+#[test]
+fn bit_fields_too_wide() {
+    let mut value = value();
+    value.flags.colour = 64;
+
+    let error = value.write(&mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn bit_fields_size() {
+    assert_eq!(flags::SIZE, 1);
+    assert_eq!(BitFields::SIZE, DATA.len());
+}
+
+#[test]
+fn bit_fields_recover() {
+    let (value, errors) = BitFields::try_read(&mut &DATA[..2]);
+
+    assert_eq!(value.flags.colour, 43);
+    assert_eq!((value.x, value.y), (0, 0));
+    assert_eq!(
+        errors.iter().map(|error| error.field).collect::<Vec<_>>(),
+        ["x", "tail"]
+    );
+}
@@ -0,0 +1,33 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/bitmasks.format")]
+pub struct Bitmasks;
+
+#[test]
+fn hex_and_binary_masks() {
+    // shifts bind tighter than masks, so the count is the low two bits of the flags
+    let data = [0x82, 5, 9, 1];
+
+    let value = Bitmasks::read(&mut &data[..]).unwrap();
+    assert_eq!(value.bonus, None);
+    assert_eq!(value.extra, Some(5));
+    assert_eq!(value.values, [9, 1]);
+    assert_eq!(value.to_bytes().unwrap(), data);
+
+    let data = [0x05, 1, 0, 4, 6];
+    let value = Bitmasks::read(&mut &data[..]).unwrap();
+    assert_eq!(value.bonus, Some(1));
+    assert_eq!(value.extra, None);
+    assert_eq!(value.values, [4]);
+    assert_eq!(value.checked, Some(6));
+    assert_eq!(value.to_bytes().unwrap(), data);
+}
+
+#[test]
+fn any_expression_as_condition() {
+    let data = [0x04, 1, 0, 7];
+
+    let value = Bitmasks::read(&mut &data[..]).unwrap();
+    assert_eq!(value.checked, None);
+    assert_eq!(value.called, Some(7));
+}
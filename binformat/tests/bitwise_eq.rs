@@ -0,0 +1,46 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/bitwise_eq.format")]
+pub struct BitwiseEq;
+
+fn data() -> Vec<u8> {
+    let nan = f32::NAN.to_le_bytes();
+    let mut data = vec![1];
+    for _ in 0..3 {
+        data.extend(nan);
+    }
+    data.push(7);
+    data.extend(f64::NAN.to_le_bytes());
+    data.extend(nan);
+    data.extend(nan);
+    data.extend(f64::NAN.to_le_bytes());
+    data
+}
+
+#[test]
+fn nan_fields_equal_themselves() {
+    let first = BitwiseEq::read(&mut &data()[..]).unwrap();
+    let second = BitwiseEq::read(&mut &data()[..]).unwrap();
+
+    assert!(first.speed.is_nan());
+    assert_eq!(first, second);
+}
+
+#[test]
+fn different_nan_payloads_are_unequal() {
+    let first = BitwiseEq::read(&mut &data()[..]).unwrap();
+    let mut second = first.clone();
+    second.samples[0].weight = f32::from_bits(f32::NAN.to_bits() | 1);
+
+    assert_ne!(first, second);
+}
+
+#[test]
+fn signed_zeros_are_unequal() {
+    let mut first = BitwiseEq::read(&mut &data()[..]).unwrap();
+    first.bonus = Some(0.0);
+    let mut second = first.clone();
+    second.bonus = Some(-0.0);
+
+    assert_ne!(first, second);
+}
@@ -0,0 +1,19 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/byteswap.format")]
+pub struct Byteswap;
+
+#[test]
+fn byteswap_round_trip() {
+    let data = [1, 2, 3, 4, 1, 2, 3, 4, 63, 192, 0, 0, 0, 1, 255, 254];
+    let parsed = Byteswap::read(&mut &data[..]).unwrap();
+
+    assert_eq!(parsed.normal, 0x04030201);
+    assert_eq!(parsed.swapped, 0x04030201u32.swap_bytes());
+    assert_eq!(parsed.swapped_float, 1.5);
+    assert_eq!(parsed.swapped_pair, (1, -2));
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
@@ -0,0 +1,29 @@
+use binformat::format_source;
+use std::io::ErrorKind;
+
+#[format_source("tests/formats/chars.format")]
+pub struct Chars;
+
+#[test]
+fn chars_roundtrip() {
+    let data = b"AJS+";
+
+    let value = Chars::read(&mut &data[..]).unwrap();
+    assert_eq!(value.grade, 'A');
+    assert_eq!(value.initials, ['J', 'S']);
+    assert_eq!(value.suffix, Some('+'));
+    assert_eq!(value.to_bytes().unwrap(), data);
+    assert_eq!(Chars::size_hint(), (3, Some(4)));
+}
+
+#[test]
+fn wide_chars_cant_be_written() {
+    let value = Chars {
+        grade: 'B',
+        initials: vec!['J', '→'],
+        suffix: None,
+    };
+
+    let error = value.to_bytes().unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
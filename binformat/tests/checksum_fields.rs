@@ -0,0 +1,66 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/checksum_fields.format")]
+pub struct ChecksumFields;
+
+#[format_source("tests/formats/late_checksum.format")]
+pub struct LateChecksum;
+
+/// crc32 of every byte before the last checksum
+const CRC: u32 = 0x6f95_4ff6;
+
+fn value() -> ChecksumFields {
+    // the ascii "123456789" has the crc32 0xcbf43926
+    ChecksumFields {
+        tag: *b"ab",
+        data: *b"123456789",
+        data_crc: 0,
+        crc: 0,
+    }
+}
+
+#[test]
+fn checksums_are_written_over_their_range() {
+    let written = value().to_bytes().unwrap();
+    assert_eq!(&written[..15], b"ab123456789\x26\x39\xf4\xcb");
+
+    let read = ChecksumFields::read(&mut written.as_slice()).unwrap();
+    assert_eq!(read.data_crc, 0xcbf4_3926);
+    assert_eq!(read.crc.to_le_bytes(), written[15..]);
+    assert_eq!(read.crc, CRC);
+}
+
+#[test]
+fn checksums_only_cover_their_range() {
+    let mut other = value();
+    other.tag = *b"xy";
+    let written = other.to_bytes().unwrap();
+
+    assert_eq!(&written[11..15], b"\x26\x39\xf4\xcb");
+    assert_ne!(written[15..], value().to_bytes().unwrap()[15..]);
+}
+
+#[test]
+fn checksums_detect_corruption() {
+    for index in [0, 5, 12] {
+        let mut written = value().to_bytes().unwrap();
+        written[index] ^= 0x10;
+
+        let error = ChecksumFields::read(&mut written.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn checksum_ranges_end_before_the_checksum() {
+    let value = LateChecksum {
+        first: 1,
+        crc: 0,
+        rest: [2; 4],
+    };
+    let error = value.to_bytes().unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+
+    let error = LateChecksum::read(&mut &[1; 9][..]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,45 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/computed.format")]
+pub struct Computed;
+
+const DATA: [u8; 8] = [2, 0, 50, 1, 0, 5, 1, 9];
+
+#[test]
+fn computed_fields_are_read() {
+    let value = Computed::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(
+        value.purses,
+        vec![
+            purse {
+                gold: 2,
+                silver: 50,
+                worth: 250,
+            },
+            purse {
+                gold: 1,
+                silver: 5,
+                worth: 105,
+            },
+        ]
+    );
+    assert!(value.bonus);
+    assert_eq!(value.level, Some(9));
+    assert_eq!(value.total, 355);
+}
+
+#[test]
+fn computed_fields_are_not_written() {
+    let mut value = Computed::read(&mut &DATA[..]).unwrap();
+    value.total = 0;
+
+    let mut bytes = Vec::new();
+    value.write(&mut bytes).unwrap();
+    assert_eq!(bytes, DATA);
+}
+
+#[test]
+fn computed_fields_take_no_space() {
+    assert_eq!(purse::SIZE, 3);
+}
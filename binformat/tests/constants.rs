@@ -0,0 +1,26 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/constants.format")]
+pub struct Constants;
+
+#[test]
+fn constants_in_expressions() {
+    let data = [4, 7, 0, 3, 10, 0, 0, 0, 1];
+
+    let value = Constants::read(&mut data.as_slice()).unwrap();
+    assert_eq!(value.bonus, Some(7));
+    assert_eq!(
+        value.rewards,
+        vec![
+            reward {
+                kind: 3,
+                amount: Some(10)
+            },
+            reward {
+                kind: 1,
+                amount: None
+            },
+        ]
+    );
+    assert_eq!(value.to_bytes().unwrap(), data);
+}
@@ -0,0 +1,42 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/context_paths.format")]
+pub struct ContextPaths;
+
+#[test]
+fn condition_on_context_path() {
+    let data = [1, 0, 1, 2, 10, 0, 20, 0, 11, 0, 21, 0];
+
+    let value = ContextPaths::read(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        value.entries,
+        vec![
+            entry {
+                id: 10,
+                bonus: Some(20),
+            },
+            entry {
+                id: 11,
+                bonus: Some(21),
+            },
+        ]
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn condition_on_context_path_unset() {
+    let data = [1, 0, 0, 2, 10, 0, 11, 0];
+
+    let value = ContextPaths::read(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        value.entries[1],
+        entry {
+            id: 11,
+            bonus: None
+        }
+    );
+}
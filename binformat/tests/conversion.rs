@@ -0,0 +1,53 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/conversion_v1.format")]
+pub struct ConversionV1;
+
+#[format_source("tests/formats/conversion_v2.format")]
+pub struct ConversionV2;
+
+#[test]
+fn convert_v1_to_v2() {
+    let v1 = ConversionV1 {
+        version: 1,
+        gold: 500,
+        num_items: 2,
+        items: vec![3, 4],
+        removed: 9,
+    };
+
+    let v2 = ConversionV2::from(v1);
+    assert_eq!(
+        v2,
+        ConversionV2 {
+            version: 2,
+            gold: 500,
+            num_items: 2,
+            items: vec![3, 4],
+            gems: 10,
+            has_bonus: false,
+        }
+    );
+}
+
+#[format_source("tests/formats/included_conversion.format")]
+pub struct IncludedConversion;
+
+#[test]
+fn convert_with_included_types() {
+    let v1 = ConversionV1 {
+        version: 1,
+        gold: 500,
+        num_items: 1,
+        items: vec![3],
+        removed: 9,
+    };
+
+    let converted = IncludedConversion::from(v1);
+    assert_eq!(converted.version, 3);
+    assert_eq!(converted.origin, point { x: 1, y: 2 });
+    assert_eq!(
+        IncludedConversion::read(&mut converted.to_bytes().unwrap().as_slice()).unwrap(),
+        converted
+    );
+}
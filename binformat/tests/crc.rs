@@ -0,0 +1,40 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/crc.format")]
+pub struct Crc;
+
+fn value() -> Crc {
+    // bytes are the ascii "123456789", whose crc32 is 0xcbf43926
+    Crc {
+        first: b'1',
+        second: (b'2', b'3', b'4', b'5'),
+        rest: vec![
+            inner {
+                value: u16::from_le_bytes(*b"67"),
+            },
+            inner {
+                value: u16::from_le_bytes(*b"89"),
+            },
+        ],
+    }
+}
+
+#[test]
+fn crc_round_trip() {
+    let mut written = Vec::new();
+    value().write(&mut written).unwrap();
+    assert_eq!(written, b"123456789\x26\x39\xf4\xcb");
+
+    assert_eq!(Crc::read(&mut written.as_slice()).unwrap(), value());
+    assert_eq!(Crc::size_hint(), (13, Some(13)));
+}
+
+#[test]
+fn crc_detects_corruption() {
+    let mut written = Vec::new();
+    value().write(&mut written).unwrap();
+    written[3] ^= 0x10;
+
+    let error = Crc::read(&mut written.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
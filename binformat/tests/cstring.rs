@@ -0,0 +1,31 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/cstring.format")]
+pub struct CString;
+
+#[test]
+fn cstring_round_trip() {
+    let data = b"hero\0\x07\0";
+    let parsed = CString::read(&mut &data[..]).unwrap();
+
+    assert_eq!(parsed.name, "hero");
+    assert_eq!(parsed.level, 7);
+    assert_eq!(parsed.guild, "");
+    assert_eq!(parsed.to_bytes().unwrap(), data);
+}
+
+#[test]
+fn cstring_without_terminator() {
+    assert!(CString::read(&mut &b"hero\0\x07guild"[..]).is_err());
+}
+
+#[test]
+fn cstring_containing_nul() {
+    let value = CString {
+        name: "he\0ro".to_owned(),
+        level: 0,
+        guild: String::new(),
+    };
+
+    assert!(value.to_bytes().is_err());
+}
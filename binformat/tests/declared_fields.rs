@@ -0,0 +1,60 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/declared_fields.format")]
+#[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DeclaredFields {
+    /// Gold held by the player
+    pub gold: u32,
+    #[serde(skip)]
+    pub item_count: u8,
+    pub items: Vec<item>,
+    /// Not part of the format, so defaulted when reading and ignored when writing
+    #[serde(skip)]
+    pub edited: bool,
+}
+
+const DATA: [u8; 11] = [100, 0, 0, 0, 2, 1, 0, 5, 2, 0, 9];
+
+#[test]
+fn declared_fields_are_read_and_written() {
+    let mut value = DeclaredFields::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(value.gold, 100);
+    assert_eq!(value.items[1], item { id: 2, count: 9 });
+    assert!(!value.edited);
+
+    value.edited = true;
+    assert_eq!(value.to_bytes().unwrap(), DATA);
+}
+
+#[test]
+fn declared_attributes_are_kept() {
+    let value = DeclaredFields::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(
+        serde_json::to_string(&value).unwrap(),
+        r#"{"gold":100,"items":[{"id":1,"count":5},{"id":2,"count":9}]}"#
+    );
+}
+
+#[format_source("tests/formats/declared_defaults.format")]
+#[derive(Debug)]
+pub struct DeclaredDefaults {
+    pub speed: f32,
+    pub flags: [u8; 40],
+    pub edited: bool,
+}
+
+#[test]
+fn declared_fields_keep_generated_impls() {
+    let mut value = DeclaredDefaults::default();
+    assert_eq!(value.flags, [0; 40]);
+    assert!(!value.edited);
+
+    value.speed = f32::NAN;
+    let read = DeclaredDefaults::read(&mut value.to_bytes().unwrap().as_slice()).unwrap();
+    assert_eq!(read, value);
+
+    value.edited = true;
+    assert_ne!(read, value);
+}
@@ -0,0 +1,43 @@
+use binformat::format_source;
+use std::io::Read;
+
+#[format_source("tests/formats/delimited_string.format")]
+pub struct DelimitedString;
+
+/// Reader which only ever returns a single byte at a time, to split terminators across reads
+struct ByteReader<'a>(&'a [u8]);
+
+impl Read for ByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0
+            .take(1)
+            .read(buf)
+            .inspect(|&read| self.0 = &self.0[read..])
+    }
+}
+
+#[test]
+fn delimited_string_round_trip() {
+    let data = b"player one\r\n\x05\x01sir\xff\x00";
+    let parsed = DelimitedString::read(&mut ByteReader(data)).unwrap();
+
+    assert_eq!(parsed.name, "player one");
+    assert_eq!(parsed.level, 5);
+    assert_eq!(parsed.title.as_deref(), Some("sir"));
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn delimited_string_containing_terminator() {
+    let value = DelimitedString {
+        name: "two\r\nlines".to_owned(),
+        level: 0,
+        has_title: 0,
+        title: None,
+    };
+
+    assert!(value.write(&mut Vec::new()).is_err());
+}
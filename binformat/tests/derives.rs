@@ -0,0 +1,26 @@
+use binformat::format_source;
+use std::collections::HashSet;
+
+#[format_source("tests/formats/derives.format")]
+pub struct Derives;
+
+#[test]
+fn clone_is_always_derived() {
+    let value = Derives::read(&mut &b"\x01\x02\x00\x03\x00"[..]).unwrap();
+
+    let mut edited = value.clone();
+    edited.points[0].x = 7;
+    assert_ne!(edited, value);
+    assert_eq!(value.points[0], point { x: 2, y: 3 });
+}
+
+#[test]
+fn extra_derives() {
+    let value = Derives::default();
+    assert_eq!(value.count, 0);
+    assert!(value.points.is_empty());
+
+    let points: HashSet<_> = [point { x: 1, y: 2 }, point { x: 1, y: 2 }].into();
+    assert_eq!(points.len(), 1);
+    assert!(point { x: 1, y: 2 } < point { x: 2, y: 0 });
+}
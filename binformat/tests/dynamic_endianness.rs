@@ -0,0 +1,35 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/dynamic_endianness.format")]
+pub struct DynamicEndianness;
+
+#[test]
+fn endianness_by_index() {
+    let data = [0, 1, 0, 0, 0, 1, 0, 0, 2, 3, 0, 0, 4];
+    let parsed = DynamicEndianness::read(&mut &data[..]).unwrap();
+
+    assert_eq!(parsed.single, 1);
+    assert_eq!(parsed.values, vec![1, 2, 3, 4]);
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn endianness_by_field() {
+    let value = DynamicEndianness {
+        order: 1,
+        single: 1,
+        values: vec![0; 4],
+    };
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written[..5], [1, 0, 0, 0, 1]);
+
+    assert_eq!(
+        DynamicEndianness::read(&mut written.as_slice()).unwrap(),
+        value
+    );
+}
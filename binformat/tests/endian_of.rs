@@ -0,0 +1,49 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/endian_of.format")]
+pub struct EndianOf;
+
+fn round_trip(data: &[u8]) -> EndianOf {
+    let value = EndianOf::read(&mut &data[..]).unwrap();
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+
+    value
+}
+
+#[test]
+fn little_endian_selected() {
+    let value = round_trip(&[0, 1, 0, 0, 0, 2, 0, 3, 0, b'a', 0, 4, 0, 1, 5, 0, 6, 0]);
+
+    assert_eq!(value.single, 1);
+    assert_eq!(value.values, vec![2, 3]);
+    assert_eq!(value.little, 4);
+    assert_eq!(
+        value.pair,
+        pair {
+            order: 1,
+            first: 0x0500,
+            second: 0x0600
+        }
+    );
+}
+
+#[test]
+fn big_endian_selected() {
+    let value = round_trip(&[1, 0, 0, 0, 1, 0, 2, 0, 3, b'a', 0, 4, 0, 0, 5, 0, 6, 0]);
+
+    assert_eq!(value.single, 1);
+    assert_eq!(value.values, vec![2, 3]);
+    // fields choosing their own byte order aren't affected
+    assert_eq!(value.little, 4);
+    assert_eq!(
+        value.pair,
+        pair {
+            order: 0,
+            first: 5,
+            second: 6
+        }
+    );
+}
@@ -0,0 +1,67 @@
+use binformat::format_source;
+use std::{fmt, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Resource {
+    Gold,
+    Gems,
+    Wood,
+}
+
+impl FromStr for Resource {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gold" => Ok(Resource::Gold),
+            "gems" => Ok(Resource::Gems),
+            "wood" => Ok(Resource::Wood),
+            _ => Err(()),
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Resource::Gold => "gold",
+            Resource::Gems => "gems",
+            Resource::Wood => "wood",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[format_source("tests/formats/enums.format")]
+pub struct Enums;
+
+const DATA: &[u8] = b"\x03wood\0gold\0gems\0\x01\x02\x00";
+
+#[test]
+fn enum_round_trip() {
+    let parsed = Enums::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(parsed.first, Resource::Gold);
+    assert_eq!(parsed.second, Resource::Gems);
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, DATA);
+}
+
+#[test]
+fn enum_unknown_string() {
+    let data = b"\x03wood\0gold\0iron\0\x02\x00\x00";
+    assert!(Enums::read(&mut &data[..]).is_err());
+}
+
+#[test]
+fn enum_missing_from_table() {
+    let value = Enums {
+        num_names: 1,
+        names: vec!["gold".to_owned()],
+        first: Resource::Gold,
+        second: Resource::Wood,
+    };
+    assert!(value.write(&mut Vec::new()).is_err());
+}
@@ -0,0 +1,38 @@
+use binformat::format_source;
+use std::io::ErrorKind;
+
+#[format_source("tests/formats/eof_repeat.format")]
+pub struct EofRepeat;
+
+#[test]
+fn eof_repeat_round_trip() {
+    let data = b"\x02\x01\x0a\x00\x02\x14\x00\x03\x1e\x00";
+    let parsed = EofRepeat::read(&mut &data[..]).unwrap();
+
+    assert_eq!(parsed.version, 2);
+    assert_eq!(
+        parsed.records,
+        vec![
+            record { id: 1, score: 10 },
+            record { id: 2, score: 20 },
+            record { id: 3, score: 30 }
+        ]
+    );
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn eof_repeat_empty() {
+    let parsed = EofRepeat::read(&mut &b"\x02"[..]).unwrap();
+    assert!(parsed.records.is_empty());
+}
+
+#[test]
+fn eof_repeat_partial_element() {
+    // ending part way through an element isn't a clean end
+    let error = EofRepeat::read(&mut &b"\x02\x01\x0a\x00\x02\x14"[..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+}
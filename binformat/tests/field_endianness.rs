@@ -0,0 +1,22 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/field_endianness.format")]
+pub struct FieldEndianness;
+
+const DATA: [u8; 10] = *b"\x12\x34\x56\x78\x00\x02\x03\x00\x04\x00";
+
+#[test]
+fn fields_override_endianness() {
+    let value = FieldEndianness::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(value.magic, 0x1234_5678);
+    assert_eq!(value.size, 2);
+    assert_eq!(value.body, 3);
+    assert_eq!(value.swapped, 4);
+}
+
+#[test]
+fn overrides_roundtrip() {
+    let value = FieldEndianness::read(&mut &DATA[..]).unwrap();
+    assert_eq!(value.to_bytes().unwrap(), DATA);
+}
@@ -0,0 +1,104 @@
+use binformat::format_source;
+use std::io::ErrorKind;
+
+#[format_source("tests/formats/field_errors.format")]
+pub struct FieldErrors;
+
+#[format_source("tests/formats/field_errors_footer.format")]
+pub struct FieldErrorsFooter;
+
+fn value() -> FieldErrors {
+    FieldErrors {
+        count: 2,
+        values: vec![10, 20],
+        inner: inner { a: 7, b: 8 },
+    }
+}
+
+/// Gets the field named by a read error
+fn failed_field(error: std::io::Error) -> &'static str {
+    error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<FieldErrorsFieldError>())
+        .unwrap()
+        .field
+}
+
+/// Gets the offset of the field named by a read error
+fn failed_offset(error: std::io::Error) -> usize {
+    error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<FieldErrorsFieldError>())
+        .unwrap()
+        .offset
+}
+
+#[test]
+fn complete_read() {
+    let bytes = value().to_bytes().unwrap();
+    assert_eq!(FieldErrors::read(&mut bytes.as_slice()).unwrap(), value());
+}
+
+#[test]
+fn truncated_read() {
+    let bytes = value().to_bytes().unwrap();
+
+    let error = FieldErrors::read(&mut &bytes[..3]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    assert_eq!(failed_field(error), "values");
+
+    let error = FieldErrors::read(&mut &bytes[..7]).unwrap_err();
+    assert_eq!(failed_field(error), "inner");
+}
+
+#[test]
+fn bad_checksum() {
+    let mut bytes = value().to_bytes().unwrap();
+    *bytes.last_mut().unwrap() ^= 1;
+
+    let error = FieldErrors::read(&mut bytes.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert_eq!(failed_field(error), "trailer");
+}
+
+#[test]
+fn error_offsets() {
+    let mut bytes = value().to_bytes().unwrap();
+
+    let error = FieldErrors::read(&mut &bytes[..3]).unwrap_err();
+    assert_eq!(failed_offset(error), 1);
+
+    let error = FieldErrors::read(&mut &bytes[..7]).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "failed to read `inner` at byte 5: failed to fill whole buffer"
+    );
+
+    *bytes.last_mut().unwrap() ^= 1;
+    let error = FieldErrors::read(&mut bytes.as_slice()).unwrap_err();
+    assert_eq!(failed_offset(error), 10);
+}
+
+#[test]
+fn error_offsets_with_footer() {
+    let value = FieldErrorsFooter {
+        version: 1,
+        entries: vec![2, 3],
+        flags: 4,
+    };
+    let bytes = value.to_bytes().unwrap();
+    assert_eq!(
+        FieldErrorsFooter::read(&mut std::io::Cursor::new(&bytes)).unwrap(),
+        value
+    );
+
+    // the footer still says there are two entries, so `flags` is cut short
+    let mut truncated = bytes[..6].to_vec();
+    truncated.extend_from_slice(&bytes[bytes.len() - 4..]);
+    let error = FieldErrorsFooter::read(&mut std::io::Cursor::new(&truncated)).unwrap_err();
+    let error = error
+        .get_ref()
+        .and_then(|inner| inner.downcast_ref::<FieldErrorsFooterFieldError>())
+        .unwrap();
+    assert_eq!((error.field, error.offset), ("flags", 5));
+}
@@ -0,0 +1,43 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/fixed_arrays.format")]
+pub struct FixedArrays;
+
+#[format_source("tests/formats/fixed_arrays_be.format")]
+pub struct FixedArraysBe;
+
+#[test]
+fn little_endian_arrays() {
+    let value = FixedArrays {
+        weights: [1.0, -2.0, 0.5, 0.0],
+        offsets: [1, -1, 256],
+        history: vec![[1, 2], [3, 4]],
+    };
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(
+        written,
+        [0, 0, 128, 63, 0, 0, 0, 192, 0, 0, 0, 63, 0, 0, 0, 0, 1, 0, 255, 255, 0, 1, 1, 2, 3, 4]
+    );
+
+    assert_eq!(FixedArrays::read(&mut written.as_slice()).unwrap(), value);
+    assert_eq!(FixedArrays::size_hint(), (26, Some(26)));
+}
+
+#[test]
+fn big_endian_arrays() {
+    let value = FixedArraysBe {
+        weights: [1.0, -2.0, 0.5, 0.0],
+        offsets: [1, -1, 256],
+    };
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(
+        written,
+        [63, 128, 0, 0, 192, 0, 0, 0, 63, 0, 0, 0, 0, 0, 0, 0, 0, 1, 255, 255, 1, 0]
+    );
+
+    assert_eq!(FixedArraysBe::read(&mut written.as_slice()).unwrap(), value);
+}
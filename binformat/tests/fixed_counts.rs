@@ -0,0 +1,39 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/fixed_counts.format")]
+pub struct FixedCounts;
+
+#[test]
+fn literal_counts_are_arrays() {
+    let data = [1, 1, 0, 2, 0, 3, 0, 4, 255, 5, 6, 7, 0, 8, 9];
+
+    let value = FixedCounts::read(&mut data.as_slice()).unwrap();
+    let fixed: [u16; 3] = value.fixed;
+    let pairs: [(u8, i8); 2] = value.pairs;
+    assert_eq!(fixed, [1, 2, 3]);
+    assert_eq!(pairs, [(4, -1), (5, 6)]);
+
+    // composite types from the format become arrays too, but non-literal counts are still vecs
+    let dynamic: &Vec<u16> = &value.dynamic;
+    let entries: &[entry; 2] = &value.entries;
+    assert_eq!(dynamic, &vec![7]);
+    assert_eq!(entries, &[entry { id: 8 }, entry { id: 9 }]);
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn truncated_composite_array() {
+    let data = [1, 1, 0, 2, 0, 3, 0, 4, 255, 5, 6, 7, 0, 8];
+
+    let error = FixedCounts::read(&mut data.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn composite_array_size_hint() {
+    // everything but `dynamic` has a fixed size
+    assert_eq!(FixedCounts::size_hint(), (13, None));
+}
@@ -0,0 +1,13 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/flatten_accessors.format")]
+pub struct FlattenAccessors;
+
+#[test]
+fn flattened_accessors() {
+    let data = [3, 1, 2, 9, 100, 0, 0, 0];
+    let parsed = FlattenAccessors::read(&mut &data[..]).unwrap();
+
+    assert_eq!(*parsed.header_flags(), 0x0103);
+    assert_eq!(*parsed.header_version_major(), 2);
+}
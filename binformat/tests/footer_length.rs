@@ -0,0 +1,50 @@
+use binformat::format_source;
+use std::io::Cursor;
+
+#[format_source("tests/formats/footer_length.format")]
+pub struct FooterLength;
+
+#[test]
+fn footer_round_trip() {
+    let data = [2, 1, 10, 0, 2, 20, 0, 7, 6, 0, 0, 0];
+
+    let value = FooterLength::read(&mut Cursor::new(data)).unwrap();
+    assert_eq!(
+        value,
+        FooterLength {
+            version: 2,
+            entries: vec![entry { id: 1, value: 10 }, entry { id: 2, value: 20 }],
+            flags: 7,
+        }
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn footer_reads_whole_struct() {
+    let mut reader = Cursor::new([0, 0, 0, 0, 0, 0]);
+
+    let value = FooterLength::read(&mut reader).unwrap();
+    assert_eq!(value.entries, vec![]);
+    assert_eq!(reader.position(), 6);
+}
+
+#[test]
+fn footer_length_past_end() {
+    let data = [2, 1, 10, 0, 7, 9, 0, 0, 0];
+
+    let error = FooterLength::read(&mut Cursor::new(data)).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn footer_from_bytes() {
+    let data = [2, 1, 10, 0, 2, 20, 0, 7, 6, 0, 0, 0];
+
+    let value = FooterLength::from_bytes(&data).unwrap();
+    assert_eq!(value.entries.len(), 2);
+    assert_eq!(value.to_bytes().unwrap(), data);
+}
@@ -0,0 +1,33 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/getters.format")]
+pub struct Getters;
+
+const DATA: [u8; 8] = *b"\x01\x05\x00\x01\x02\x00\x03\x00";
+
+#[test]
+fn getters_return_references() {
+    let value = Getters::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(*value.flags(), 1);
+    assert_eq!(value.bonus(), Some(&5));
+    assert_eq!(value.points(), &[point { x: 2, y: 3 }][..]);
+    assert_eq!(*value.points()[0].y(), 3);
+}
+
+#[test]
+fn missing_conditional_field() {
+    let value = Getters::read(&mut &b"\x00\x00"[..]).unwrap();
+
+    assert_eq!(value.bonus(), None);
+    assert!(value.points().is_empty());
+}
+
+#[test]
+fn private_fields_still_roundtrip() {
+    let value = Getters::read(&mut &DATA[..]).unwrap();
+
+    let mut bytes = Vec::new();
+    value.write(&mut bytes).unwrap();
+    assert_eq!(bytes, DATA);
+}
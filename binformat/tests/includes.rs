@@ -0,0 +1,43 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/includes.format")]
+pub struct Includes;
+
+const DATA: [u8; 8] = [1, 2, 3, 4, 0x10, 0x20, 0x30, 0xff];
+
+#[test]
+fn included_types_are_usable() {
+    let value = Includes::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(
+        value.line,
+        line {
+            start: point { x: 1, y: 2 },
+            end: point { x: 3, y: 4 },
+        }
+    );
+}
+
+#[test]
+fn local_types_override_included_ones() {
+    let value = Includes::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(
+        value.colour,
+        colour {
+            r: 0x10,
+            g: 0x20,
+            b: 0x30,
+            a: 0xff,
+        }
+    );
+}
+
+#[test]
+fn included_types_roundtrip() {
+    let value = Includes::read(&mut &DATA[..]).unwrap();
+
+    let mut bytes = Vec::new();
+    value.write(&mut bytes).unwrap();
+    assert_eq!(bytes, DATA);
+}
@@ -0,0 +1,27 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/large_default.format")]
+pub struct LargeDefault;
+
+#[test]
+fn zeroed_default() {
+    let value = LargeDefault::default();
+
+    assert_eq!(value.version, 0);
+    assert_eq!(value.data, [0; 256]);
+    assert_eq!(value.small, small { values: [0; 4] });
+    assert_eq!(value.large.values, [[0; 2]; 40]);
+    assert_eq!(value.pairs, (0, [0; 33]));
+}
+
+#[test]
+fn zeroed_default_round_trip() {
+    let value = LargeDefault {
+        version: 2,
+        ..Default::default()
+    };
+
+    let written = value.to_bytes().unwrap();
+    assert_eq!(written.len(), 2 + 256 + 8 + 80 + 1 + 132);
+    assert_eq!(LargeDefault::read(&mut written.as_slice()).unwrap(), value);
+}
@@ -0,0 +1,57 @@
+use binformat::format_source;
+use std::io::ErrorKind;
+
+#[format_source("tests/formats/len_prefix.format")]
+pub struct LenPrefix;
+
+const DATA: [u8; 15] = [
+    2, 0x12, 0x34, 0x56, 0x78, // values
+    0, 2, 1, 0xff, 3, 4, // points
+    1, 0, 9,    // flags
+    0xaa, // trailing
+];
+
+#[test]
+fn len_prefix_read() {
+    let mut reader = &DATA[..];
+    let value = LenPrefix::read(&mut reader).unwrap();
+
+    assert_eq!(value.values, vec![0x1234, 0x5678]);
+    assert_eq!(
+        value.points,
+        vec![point { x: 1, y: -1 }, point { x: 3, y: 4 }]
+    );
+    assert_eq!(value.flags, vec![9]);
+    assert_eq!(reader, [0xaa]);
+}
+
+#[test]
+fn len_prefix_written_from_elements() {
+    let mut value = LenPrefix::read(&mut &DATA[..]).unwrap();
+    value.values.push(0x9abc);
+    value.points.clear();
+
+    let mut bytes = Vec::new();
+    value.write(&mut bytes).unwrap();
+    assert_eq!(
+        bytes,
+        [3, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0, 0, 1, 0, 9]
+    );
+}
+
+#[test]
+fn len_prefix_too_long() {
+    let value = LenPrefix {
+        values: vec![0; 256],
+        points: Vec::new(),
+        flags: Vec::new(),
+    };
+
+    let error = value.write(&mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn len_prefix_size_hint() {
+    assert_eq!(LenPrefix::size_hint(), (5, None));
+}
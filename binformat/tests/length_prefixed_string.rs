@@ -0,0 +1,54 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/length_prefixed_string.format")]
+pub struct LengthPrefixedString;
+
+#[test]
+fn length_prefixed_string_round_trip() {
+    let data = b"\x0aplayer one\x03\x00\x05sir";
+    let parsed = LengthPrefixedString::read(&mut &data[..]).unwrap();
+
+    assert_eq!(parsed.name, "player one");
+    assert_eq!(parsed.level, 5);
+    assert_eq!(parsed.title, "sir");
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn length_prefixed_string_lengths() {
+    let mut value = LengthPrefixedString {
+        name_len: 0,
+        name: "renamed".to_owned(),
+        title_len: 3,
+        level: 1,
+        title: "sir".to_owned(),
+    };
+
+    // lengths from `length_of` follow the string, others have to be kept up to date
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, b"\x07renamed\x03\x00\x01sir");
+
+    value.title = "madam".to_owned();
+    assert!(value.write(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn length_prefixed_string_invalid_utf8() {
+    let data = b"\x01\xff\x00\x00\x00";
+    let error = LengthPrefixedString::read(&mut &data[..]).unwrap_err();
+
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn length_prefixed_string_truncated() {
+    // the length is far more than the data left
+    let data = b"\x00\xff\xff\x05sir";
+    let error = LengthPrefixedString::read(&mut &data[..]).unwrap_err();
+
+    assert_eq!(error.kind(), std::io::ErrorKind::UnexpectedEof);
+}
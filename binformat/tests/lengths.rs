@@ -0,0 +1,38 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/lengths.format")]
+pub struct Lengths;
+
+#[test]
+fn length_round_trip() {
+    let data = [3, 0, 1, 7, 0, 0, 0, 10, 20, 30];
+
+    let value = Lengths::read(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        value,
+        Lengths {
+            count: 3,
+            version: 1,
+            flags: 7,
+            values: vec![10, 20, 30],
+        }
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn length_from_target() {
+    let value = Lengths {
+        count: 0,
+        version: 1,
+        flags: 7,
+        values: vec![10, 20],
+    };
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, [2, 0, 1, 7, 0, 0, 0, 10, 20]);
+}
@@ -0,0 +1,48 @@
+use binformat::format_source;
+use std::io::ErrorKind;
+
+#[format_source("tests/formats/magic.format")]
+pub struct Magic;
+
+#[format_source("tests/formats/magic_bytes.format")]
+pub struct MagicBytes;
+
+#[test]
+fn magic_is_checked() {
+    let value = Magic::read(&mut &b"RGSV\x34\x12"[..]).unwrap();
+    assert_eq!(value.value, 0x1234);
+
+    let error = Magic::read(&mut &b"RGSX\x34\x12"[..]).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn magic_is_written() {
+    let bytes = Magic { value: 0x1234 }.to_bytes().unwrap();
+    assert_eq!(bytes, b"RGSV\x34\x12");
+    assert_eq!(Magic::size_hint(), (6, Some(6)));
+}
+
+#[test]
+fn try_read_records_wrong_magic() {
+    let (value, errors) = Magic::try_read(&mut &b"RGSX\x34\x12"[..]);
+
+    assert_eq!(value.value, 0x1234);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].field, "magic");
+}
+
+#[test]
+fn magic_as_bytes() {
+    let bytes = MagicBytes { value: 7 }.to_bytes().unwrap();
+    assert_eq!(&bytes[..4], b"\x89\x50\x00\x07");
+    assert_eq!(MagicBytes::read(&mut bytes.as_slice()).unwrap().value, 7);
+
+    let error = MagicBytes::read(&mut &b"\x89\x51\x00\x07\x00\x00\x00\x00"[..]).unwrap_err();
+    let error = error
+        .get_ref()
+        .unwrap()
+        .downcast_ref::<MagicBytesFieldError>()
+        .unwrap();
+    assert_eq!(error.field, "magic");
+}
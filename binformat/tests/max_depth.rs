@@ -0,0 +1,31 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/max_depth.format")]
+pub struct MaxDepth;
+
+#[test]
+fn within_max_depth() {
+    let data = [1, 10, 2, 20, 0, 30, 1, 40, 0];
+
+    let value = MaxDepth::read(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        value.tree.children[1].children,
+        vec![node {
+            value: 40,
+            child_count: 0,
+            children: vec![],
+        }]
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn exceeds_max_depth() {
+    let data = [1, 10, 1, 20, 1, 30, 1, 40, 0];
+
+    let error = MaxDepth::read(&mut data.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
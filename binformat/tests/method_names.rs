@@ -0,0 +1,26 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/method_names.format")]
+pub struct MethodNames;
+
+#[test]
+fn custom_method_names() {
+    let data = [0, 0, 0, 7, 0, 1, 255, 254];
+    let parsed = MethodNames::decode(&mut &data[..]).unwrap();
+
+    assert_eq!(parsed.id, 7);
+    assert_eq!(parsed.origin, point { x: 1, y: -2 });
+
+    let mut written = Vec::new();
+    parsed.encode(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn byte_conversions_use_method_names() {
+    let data = [0, 0, 0, 7, 0, 1, 255, 254];
+    let parsed = MethodNames::from_bytes(&data).unwrap();
+
+    assert_eq!(parsed.id, 7);
+    assert_eq!(parsed.to_bytes().unwrap(), data);
+}
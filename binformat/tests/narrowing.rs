@@ -0,0 +1,55 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/narrowing.format")]
+pub struct Narrowing;
+
+#[test]
+fn narrow_round_trip() {
+    let data = [1, 200, 255, 255, 2, 0];
+
+    let value = Narrowing::read(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        value,
+        Narrowing {
+            compact: 1,
+            total: 200,
+            values: vec![-1, 2],
+        }
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+    assert_eq!(Narrowing::size_hint(), (1 + 1 + 4, Some(1 + 4 + 8)));
+}
+
+#[test]
+fn wide_round_trip() {
+    let data = [0, 0, 1, 0, 0, 255, 255, 255, 255, 2, 0, 0, 0];
+
+    let value = Narrowing::read(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        value,
+        Narrowing {
+            compact: 0,
+            total: 256,
+            values: vec![-1, 2],
+        }
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn narrow_overflow() {
+    let value = Narrowing {
+        compact: 1,
+        total: 256,
+        values: vec![0, 0],
+    };
+
+    let error = value.write(&mut Vec::new()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+}
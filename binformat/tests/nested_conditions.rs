@@ -0,0 +1,32 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/nested_conditions.format")]
+pub struct NestedConditions;
+
+#[test]
+fn conditions_on_earlier_fields_of_composite() {
+    let data = [2, 0x21, 10, 0, 20, 0, 1, 2, 0x00, 11, 0];
+
+    let value = NestedConditions::read(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        value.entries,
+        vec![
+            entry {
+                flags: 0x21,
+                id: 10,
+                bonus: Some(20),
+                extras: vec![1, 2],
+            },
+            entry {
+                flags: 0x00,
+                id: 11,
+                bonus: None,
+                extras: vec![],
+            },
+        ]
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
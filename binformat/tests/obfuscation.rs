@@ -0,0 +1,37 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/enciphered.format")]
+pub struct Enciphered;
+
+/// `hidden` is xored with the bytes of its seed (0x1234, stored little endian), and the chest's
+/// contents with its one byte key
+const ENCIPHERED_DATA: [u8; 22] = [
+    0x34, 0x12, 0x50, 0x12, 0x34, 0x12, 0x31, 0x12, 0x35, 0x10, 0x37, 0xff, 0x9b, 0xff, 0xff, 0xff,
+    0xfa, 0xff, 0xfe, 0xfd, 0xfc, 9,
+];
+
+#[test]
+fn enciphered_round_trip() {
+    let contents = sealed {
+        gold: 100,
+        gems: 5,
+        flags: [1, 2, 3],
+    };
+    let value = Enciphered {
+        seed: 0x1234,
+        hidden: contents.clone(),
+        chest: chest {
+            key: 0xff,
+            contents,
+        },
+        after: 9,
+    };
+
+    assert_eq!(value.to_bytes().unwrap(), ENCIPHERED_DATA);
+    assert_eq!(Enciphered::read(&mut &ENCIPHERED_DATA[..]).unwrap(), value);
+}
+
+#[test]
+fn enciphered_read_needs_every_byte() {
+    assert!(Enciphered::read(&mut &ENCIPHERED_DATA[..8]).is_err());
+}
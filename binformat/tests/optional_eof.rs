@@ -0,0 +1,48 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/optional_eof.format")]
+pub struct OptionalEof;
+
+const OLD: [u8; 5] = [1, 100, 0, 0, 0];
+const NEW: [u8; 9] = [2, 100, 0, 0, 0, 5, 7, 0x10, 0x0e];
+
+#[test]
+fn missing_trailing_fields_are_none() {
+    let value = OptionalEof::read(&mut &OLD[..]).unwrap();
+
+    assert_eq!(value.gold, 100);
+    assert_eq!(value.stats, None);
+    assert_eq!(value.playtime, None);
+    assert_eq!(value.to_bytes().unwrap(), OLD);
+}
+
+#[test]
+fn present_trailing_fields_are_read() {
+    let value = OptionalEof::read(&mut &NEW[..]).unwrap();
+
+    assert_eq!(
+        value.stats,
+        Some(stats {
+            strength: 5,
+            speed: 7
+        })
+    );
+    assert_eq!(value.playtime, Some(3600));
+    assert_eq!(value.to_bytes().unwrap(), NEW);
+}
+
+#[test]
+fn data_ending_part_way_through_a_field_fails() {
+    assert!(OptionalEof::read(&mut &NEW[..6]).is_err());
+}
+
+#[test]
+fn fields_after_a_missing_field_fail_to_write() {
+    let mut value = OptionalEof::read(&mut &OLD[..]).unwrap();
+    value.playtime = Some(60);
+
+    assert_eq!(
+        value.to_bytes().unwrap_err().kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+}
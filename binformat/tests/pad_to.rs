@@ -0,0 +1,36 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/pad_to.format")]
+pub struct PadTo;
+
+fn round_trip(name: &str, padding: usize) {
+    let value = PadTo {
+        name: name.to_owned(),
+        value: 0x01020304,
+        block: block { tag: 9, extra: 8 },
+    };
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+
+    let mut expected = name.as_bytes().to_vec();
+    expected.push(0);
+    expected.resize(expected.len() + padding, 0);
+    expected.extend_from_slice(&[4, 3, 2, 1, 9, 0, 0, 0, 8]);
+    assert_eq!(written[..written.len() - 4], expected);
+
+    assert_eq!(PadTo::read(&mut written.as_slice()).unwrap(), value);
+}
+
+#[test]
+fn pads_to_block() {
+    round_trip("short", 10);
+    round_trip("exactly sixteen", 0);
+    round_trip("just over sixteen", 14);
+}
+
+#[test]
+fn padded_size_hint() {
+    assert_eq!(PadTo::size_hint(), (1 + 4 + 2 + 4, None));
+    assert_eq!(block::size_hint(), (2, Some(5)));
+}
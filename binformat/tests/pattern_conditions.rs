@@ -0,0 +1,43 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/pattern_conditions.format")]
+pub struct PatternConditions;
+
+#[test]
+fn prefix_matches() {
+    let data = [b'v', b'2', b'+', 0, 3, 0, 7, 8];
+
+    let value = PatternConditions::read(&mut data.as_slice()).unwrap();
+    assert_eq!(value.name, "v2+");
+    assert_eq!(value.version, Some(3));
+    assert_eq!(
+        value.entry,
+        entry {
+            value: 7,
+            extra: Some(8)
+        }
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn prefix_doesnt_match() {
+    let data = [b'v', b'1', 0, 7];
+
+    let value = PatternConditions::read(&mut data.as_slice()).unwrap();
+    assert_eq!(value.version, None);
+    assert_eq!(
+        value.entry,
+        entry {
+            value: 7,
+            extra: None
+        }
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
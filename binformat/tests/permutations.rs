@@ -0,0 +1,45 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/permutations.format")]
+pub struct Permutations;
+
+fn body() -> body {
+    body {
+        a: 1,
+        b: 0x0302,
+        c: 0x07060504,
+        d: -1,
+    }
+}
+
+#[test]
+fn seeds_permute_fields() {
+    let first = Permutations {
+        seed: 1,
+        body: body(),
+    };
+    let second = Permutations {
+        seed: 2,
+        body: body(),
+    };
+
+    let first_bytes = first.to_bytes().unwrap();
+    let second_bytes = second.to_bytes().unwrap();
+    assert_ne!(first_bytes[4..], second_bytes[4..]);
+
+    // both orders hold the same bytes for each field
+    let mut first_sorted = first_bytes[4..].to_vec();
+    let mut second_sorted = second_bytes[4..].to_vec();
+    first_sorted.sort();
+    second_sorted.sort();
+    assert_eq!(first_sorted, second_sorted);
+
+    assert_eq!(
+        Permutations::read(&mut first_bytes.as_slice()).unwrap(),
+        first
+    );
+    assert_eq!(
+        Permutations::read(&mut second_bytes.as_slice()).unwrap(),
+        second
+    );
+}
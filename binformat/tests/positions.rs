@@ -0,0 +1,108 @@
+use binformat::format_source;
+use std::io::{ErrorKind, Read, Write};
+
+#[format_source("tests/formats/positions.format")]
+pub struct Positions;
+
+#[format_source("tests/formats/aligned_strings.format")]
+pub struct AlignedStrings;
+
+/// Reader which can't seek, so positions have to be tracked as bytes are read
+struct PlainReader<'a>(&'a [u8]);
+
+impl Read for PlainReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+/// Writer which can't seek either
+struct PlainWriter(Vec<u8>);
+
+impl Write for PlainWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn value(name: &str) -> Positions {
+    Positions {
+        kind: 7,
+        value: 0x01020304,
+        name: name.to_owned(),
+        block: block { tag: 9, value: 5 },
+    }
+}
+
+#[test]
+fn align_and_assert_len() {
+    let mut writer = PlainWriter(Vec::new());
+    value("seven!!").write(&mut writer).unwrap();
+
+    let mut expected = vec![7, 0, 0, 0, 4, 3, 2, 1];
+    expected.extend_from_slice(b"seven!!\0");
+    expected.extend_from_slice(&[9, 0, 5, 0]);
+    assert_eq!(writer.0, expected);
+
+    let read = Positions::read(&mut PlainReader(&expected)).unwrap();
+    assert_eq!(read, value("seven!!"));
+}
+
+#[test]
+fn align_after_variable_length() {
+    for (name, padding) in [("abc", 0), ("ab", 1), ("abcd", 3)] {
+        let value = AlignedStrings {
+            name: name.to_owned(),
+            value: 0x01020304,
+        };
+
+        let mut expected = name.as_bytes().to_vec();
+        expected.push(0);
+        expected.resize(expected.len() + padding, 0);
+        expected.extend_from_slice(&[4, 3, 2, 1]);
+
+        let mut writer = PlainWriter(Vec::new());
+        value.write(&mut writer).unwrap();
+        assert_eq!(writer.0, expected);
+
+        let read = AlignedStrings::read(&mut PlainReader(&expected)).unwrap();
+        assert_eq!(read, value);
+    }
+}
+
+#[test]
+fn assert_len_mismatch() {
+    let mut writer = PlainWriter(Vec::new());
+    let error = value("short").write(&mut writer).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidInput);
+
+    let mut data = vec![7, 0, 0, 0, 4, 3, 2, 1];
+    data.extend_from_slice(b"short\0");
+    data.extend_from_slice(&[9, 0, 5, 0]);
+    let error = Positions::read(&mut PlainReader(&data)).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+}
+
+#[test]
+fn asserted_size_hint() {
+    assert_eq!(
+        Positions::size_hint(),
+        (1 + 4 + 8 + 4, Some(1 + 4 + 3 + 8 + 4))
+    );
+    assert_eq!(block::size_hint(), (3, Some(4)));
+}
+
+#[test]
+fn read_counted() {
+    let mut bytes = value("seven!!").to_bytes().unwrap();
+    let len = bytes.len();
+    bytes.extend_from_slice(b"rest");
+
+    let (read, count) = Positions::read_counted(&mut PlainReader(&bytes)).unwrap();
+    assert_eq!(read, value("seven!!"));
+    assert_eq!(count, len);
+}
@@ -0,0 +1,59 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/recovery.format")]
+pub struct Recovery;
+
+fn value() -> Recovery {
+    Recovery {
+        version: 3,
+        count: 2,
+        values: vec![10, 20],
+        inner: inner { a: 7, b: 8 },
+        last: 9,
+    }
+}
+
+#[test]
+fn try_read_complete() {
+    let bytes = value().to_bytes().unwrap();
+
+    let (read, errors) = Recovery::try_read(&mut bytes.as_slice());
+    assert_eq!(read, value());
+    assert!(errors.is_empty());
+}
+
+#[test]
+fn try_read_truncated() {
+    let bytes = value().to_bytes().unwrap();
+    // keep the version, count and values, along with part of `inner`
+    let truncated = &bytes[..9];
+
+    let (read, errors) = Recovery::try_read(&mut &truncated[..]);
+    assert_eq!(
+        read,
+        Recovery {
+            version: 3,
+            count: 2,
+            values: vec![10, 20],
+            ..Default::default()
+        }
+    );
+
+    let fields: Vec<_> = errors.iter().map(|error| error.field).collect();
+    assert_eq!(fields, ["inner", "last", "trailer"]);
+    assert!(errors
+        .iter()
+        .all(|error| error.error.kind() == std::io::ErrorKind::UnexpectedEof));
+}
+
+#[test]
+fn try_read_bad_checksum() {
+    let mut bytes = value().to_bytes().unwrap();
+    *bytes.last_mut().unwrap() ^= 1;
+
+    let (read, errors) = Recovery::try_read(&mut bytes.as_slice());
+    assert_eq!(read, value());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].field, "trailer");
+    assert_eq!(errors[0].error.kind(), std::io::ErrorKind::InvalidData);
+}
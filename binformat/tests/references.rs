@@ -0,0 +1,64 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/references.format")]
+pub struct References;
+
+const DATA: &[u8] = &[3, 10, 0, 0, 0, 20, 0, 0, 0, 30, 0, 0, 0, 2, 0, 1, 1];
+
+#[test]
+fn references_resolve() {
+    let parsed = References::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(parsed.first, 2);
+    assert_eq!(parsed.resolve_first(), Some(&30));
+    assert_eq!(parsed.resolve_second(), Some(&10));
+    assert_eq!(parsed.resolve_third(), Some(&20));
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, DATA);
+}
+
+#[test]
+fn references_out_of_range() {
+    let mut data = DATA.to_vec();
+    data[13] = 3;
+
+    let error = References::read(&mut data.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn references_absent() {
+    let mut data = DATA.to_vec();
+    data[15] = 0;
+    data.pop();
+    let parsed = References::read(&mut data.as_slice()).unwrap();
+
+    assert_eq!(parsed.resolve_first(), Some(&30));
+    assert_eq!(parsed.third, None);
+    assert_eq!(parsed.resolve_third(), None);
+}
+
+#[format_source("tests/formats/nested_references.format")]
+pub struct NestedReferences;
+
+const NESTED_DATA: &[u8] = &[2, 10, 0, 20, 0, 5, 1, 7, 0];
+
+#[test]
+fn nested_references_resolve_through_root() {
+    let parsed = NestedReferences::read(&mut &NESTED_DATA[..]).unwrap();
+
+    assert_eq!(parsed.players[0].resolve_name(&parsed), Some(&20));
+    assert_eq!(parsed.players[1].resolve_name(&parsed), Some(&10));
+    assert_eq!(parsed.to_bytes().unwrap(), NESTED_DATA);
+}
+
+#[test]
+fn nested_references_out_of_range() {
+    let mut data = NESTED_DATA.to_vec();
+    data[6] = 2;
+
+    let error = NestedReferences::read(&mut data.as_slice()).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,43 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/rename.format")]
+pub struct Rename;
+
+#[test]
+fn renamed_fields() {
+    let data = [2, 0, 5, 0, 0, 0, 2, 1, 9, 0, 0];
+
+    let value = Rename::read(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        value,
+        Rename {
+            version: 2,
+            extra_data: Some(5),
+            entry_count: 2,
+            entries: vec![
+                entry {
+                    entry_kind: 1,
+                    bonus: Some(9)
+                },
+                entry {
+                    entry_kind: 0,
+                    bonus: None
+                },
+            ],
+        }
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
+
+#[test]
+fn renamed_condition_unset() {
+    let data = [1, 0, 0];
+
+    let value = Rename::read(&mut data.as_slice()).unwrap();
+    assert_eq!(value.version, 1);
+    assert_eq!(value.extra_data, None);
+    assert!(value.entries.is_empty());
+}
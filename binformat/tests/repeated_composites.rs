@@ -0,0 +1,68 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/repeated_composites.format")]
+pub struct RepeatedComposites;
+
+#[test]
+fn repeated_composites_use_context() {
+    let data = [2, 2, 1, 0, 10, 0, 2, 0, 20, 0];
+
+    let value = RepeatedComposites::read(&mut &data[..]).unwrap();
+    assert_eq!(
+        value.entries,
+        [
+            entry {
+                id: 1,
+                bonus: Some(10)
+            },
+            entry {
+                id: 2,
+                bonus: Some(20)
+            },
+        ]
+    );
+    assert!(value.extras.is_empty());
+    assert_eq!(value.to_bytes().unwrap(), data);
+}
+
+#[test]
+fn conditional_repeated_composites() {
+    let data = [3, 1, 1, 0, 10, 0, 2, 0, 20, 0];
+
+    let value = RepeatedComposites::read(&mut &data[..]).unwrap();
+    assert_eq!(
+        value.extras,
+        [entry {
+            id: 2,
+            bonus: Some(20)
+        }]
+    );
+    assert_eq!(value.to_bytes().unwrap(), data);
+
+    let data = [1, 1, 1, 0];
+    let value = RepeatedComposites::read(&mut &data[..]).unwrap();
+    assert_eq!(value.entries, [entry { id: 1, bonus: None }]);
+    assert!(value.extras.is_empty());
+    assert_eq!(value.to_bytes().unwrap(), data);
+}
+
+#[test]
+fn conditional_repeated_composites_need_their_condition() {
+    let mut value = RepeatedComposites {
+        version: 1,
+        count: 1,
+        entries: vec![entry { id: 1, bonus: None }],
+        extras: vec![entry { id: 2, bonus: None }],
+    };
+
+    // the extras would be skipped when read back, leaving their bytes to be misread
+    let error = value.to_bytes().unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidInput);
+
+    value.extras.clear();
+    let written = value.to_bytes().unwrap();
+    assert_eq!(
+        RepeatedComposites::read(&mut written.as_slice()).unwrap(),
+        value
+    );
+}
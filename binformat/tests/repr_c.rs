@@ -0,0 +1,30 @@
+use binformat::format_source;
+use std::mem::{offset_of, size_of};
+
+#[format_source("tests/formats/repr_c.format")]
+pub struct ReprC;
+
+#[test]
+fn c_layout() {
+    // declaration order is kept, with padding before each field to align it
+    assert_eq!(size_of::<inner>(), 8);
+    assert_eq!(offset_of!(inner, value), 4);
+    assert_eq!(offset_of!(ReprC, inner), 4);
+    assert_eq!(offset_of!(ReprC, last), 12);
+    assert_eq!(size_of::<ReprC>(), 20);
+}
+
+#[test]
+fn repr_c_round_trip() {
+    let value = ReprC {
+        first: 1,
+        inner: inner { tag: 2, value: 3 },
+        last: 4,
+        values: [5, 6],
+    };
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, [1, 2, 3, 0, 0, 0, 4, 5, 0, 6, 0]);
+    assert_eq!(ReprC::read(&mut written.as_slice()).unwrap(), value);
+}
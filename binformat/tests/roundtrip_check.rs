@@ -0,0 +1,27 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/roundtrip_check.format")]
+pub struct RoundtripCheck;
+
+#[test]
+fn symmetric_value() {
+    let value = RoundtripCheck {
+        flag: 1,
+        extra: Some(2),
+    };
+
+    assert_eq!(value.to_bytes().unwrap(), [1, 2, 0]);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "doesn't read back")]
+fn asymmetric_value() {
+    // extra is written but won't be read, as the flag isn't set
+    let value = RoundtripCheck {
+        flag: 0,
+        extra: Some(2),
+    };
+
+    value.to_bytes().unwrap();
+}
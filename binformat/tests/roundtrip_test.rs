@@ -0,0 +1,14 @@
+use binformat::format_source;
+
+// generates a test checking `tests/samples/roundtrip_test.bin` is written back unchanged
+#[format_source("tests/formats/roundtrip_test.format")]
+pub struct RoundtripTest;
+
+#[test]
+fn sample_is_read() {
+    let sample = include_bytes!("samples/roundtrip_test.bin");
+    let value = RoundtripTest::from_bytes(sample).unwrap();
+
+    assert_eq!(value.values, vec![5, 7]);
+    assert!(value.flag);
+}
@@ -0,0 +1,42 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/serde.format")]
+pub struct Serde;
+
+#[format_source("tests/formats/serde_context.format")]
+pub struct SerdeRooted;
+
+#[test]
+fn serde_round_trip() {
+    let value = Serde {
+        flags: 2,
+        points: vec![point { x: 1, y: -2 }, point { x: 3, y: -4 }],
+        scale: 0.5,
+    };
+
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(
+        json,
+        r#"{"flags":2,"points":[{"x":1,"y":-2},{"x":3,"y":-4}],"scale":0.5}"#
+    );
+    assert_eq!(serde_json::from_str::<Serde>(&json).unwrap(), value);
+}
+
+#[test]
+fn serde_skips_context() {
+    let data = [0, 3, 5, 0];
+    let value = SerdeRooted::read(&mut data.as_slice()).unwrap();
+
+    let json = serde_json::to_string(&value).unwrap();
+    assert_eq!(
+        json,
+        r#"{"header":{"flags":0},"bonus":null,"kind":3,"loot":{"gem":{"carats":5}}}"#
+    );
+
+    let parsed = serde_json::from_str::<SerdeRooted>(&json).unwrap();
+    assert_eq!(parsed, value);
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
@@ -0,0 +1,40 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/size_hint.format")]
+pub struct SizeHint;
+
+#[format_source("tests/formats/size_hint_unbounded.format")]
+pub struct SizeHintUnbounded;
+
+#[format_source("tests/formats/size_hint_fixed.format")]
+pub struct SizeHintFixed;
+
+#[test]
+fn bounded_size_hint() {
+    assert_eq!(pair::size_hint(), (1, Some(3)));
+    assert_eq!(SizeHint::size_hint(), (2 + 2 + 2, Some(2 + 4 + 2 + 6)));
+}
+
+#[test]
+fn unbounded_size_hint() {
+    assert_eq!(SizeHintUnbounded::size_hint(), (1, None));
+}
+
+#[test]
+fn fixed_size() {
+    assert_eq!(point::SIZE, 8);
+    assert_eq!(SizeHintFixed::SIZE, 2 + 8 + 3 + 4);
+    assert_eq!(
+        SizeHintFixed::size_hint(),
+        (SizeHintFixed::SIZE, Some(SizeHintFixed::SIZE))
+    );
+
+    let bytes = SizeHintFixed {
+        version: 1,
+        origin: point { x: 0.5, y: 1.5 },
+        flags: [true, false, true],
+    }
+    .to_bytes()
+    .unwrap();
+    assert_eq!(bytes.len(), SizeHintFixed::SIZE);
+}
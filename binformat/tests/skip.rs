@@ -0,0 +1,36 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/skip.format")]
+pub struct Skip;
+
+const DATA: [u8; 15] = *b"\xff\xff\x34\x12\xaa\xbb\xcc\x01\xdd\xee\xff\x00\x00\x00\x00";
+
+#[test]
+fn skipped_bytes_are_ignored() {
+    let value = Skip::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(value.value, 0x1234);
+    assert_eq!(value.inner, inner { flag: 1 });
+}
+
+#[test]
+fn skipped_bytes_are_written_as_zeros() {
+    let value = Skip::read(&mut &DATA[..]).unwrap();
+
+    let mut bytes = Vec::new();
+    value.write(&mut bytes).unwrap();
+    assert_eq!(
+        bytes,
+        *b"\x00\x00\x34\x12\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00"
+    );
+}
+
+#[test]
+fn skipped_bytes_must_be_present() {
+    assert!(Skip::read(&mut &DATA[..12]).is_err());
+}
+
+#[test]
+fn size_hint_includes_skipped_bytes() {
+    assert_eq!(Skip::size_hint(), (15, Some(15)));
+}
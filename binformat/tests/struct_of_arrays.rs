@@ -0,0 +1,27 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/struct_of_arrays.format")]
+pub struct StructOfArrays;
+
+#[test]
+fn struct_of_arrays_round_trip() {
+    let data = [3, 1, 0, 2, 0, 3, 0, 255, 4, 5, 9];
+
+    let value = StructOfArrays::read(&mut data.as_slice()).unwrap();
+    assert_eq!(
+        value,
+        StructOfArrays {
+            count: 3,
+            points: vec![
+                point { x: 1, y: -1 },
+                point { x: 2, y: 4 },
+                point { x: 3, y: 5 },
+            ],
+            trailer: 9,
+        }
+    );
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(written, data);
+}
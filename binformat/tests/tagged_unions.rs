@@ -0,0 +1,48 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/tagged_unions.format")]
+pub struct TaggedUnions;
+
+#[test]
+fn tagged_union_round_trip() {
+    for data in [
+        &b"\x00\x02\x10\x27\x00\x00"[..],
+        &b"\x03\x02\x01\xe8\x03"[..],
+    ] {
+        let parsed = TaggedUnions::read(&mut &data[..]).unwrap();
+
+        let mut written = Vec::new();
+        parsed.write(&mut written).unwrap();
+        assert_eq!(written, data);
+    }
+
+    let parsed = TaggedUnions::read(&mut &b"\x03\x02\x01\xe8\x03"[..]).unwrap();
+    assert_eq!(
+        parsed.loot,
+        Loot::gem(gem {
+            colour: 1,
+            carats: 1000
+        })
+    );
+}
+
+#[test]
+fn tagged_union_unknown_tag() {
+    let error = TaggedUnions::read(&mut &b"\x01\x02\x00\x00\x00\x00"[..]).unwrap_err();
+    assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn tagged_union_mismatched_tag() {
+    let value = TaggedUnions {
+        kind: 3,
+        count: 1,
+        loot: Loot::coin(coin { value: 5 }),
+    };
+    assert!(value.write(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn tagged_union_size_hint() {
+    assert_eq!(TaggedUnions::size_hint(), (5, Some(6)));
+}
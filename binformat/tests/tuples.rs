@@ -0,0 +1,25 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/tuples.format")]
+pub struct Tuples;
+
+#[test]
+fn tuple_round_trip() {
+    let value = Tuples {
+        position: (1.5, -2.0, 0.25),
+        colour: (200, true),
+        has_velocity: 1,
+        velocity: Some((0.0, 1.0, 2.0)),
+        path: vec![(-1, (2, 3)), (4, (5, 6))],
+    };
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    assert_eq!(
+        written[..14],
+        [63, 192, 0, 0, 192, 0, 0, 0, 62, 128, 0, 0, 200, 1]
+    );
+    assert_eq!(written[written.len() - 8..], [255, 255, 2, 3, 0, 4, 5, 6]);
+
+    assert_eq!(Tuples::read(&mut written.as_slice()).unwrap(), value);
+}
@@ -0,0 +1,42 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/until_repeat.format")]
+pub struct UntilRepeat;
+
+const DATA: &[u8] = b"\xff\x05\x00\x07\x00\x00\x00\x01\x0a\x00\xff\x00\x00\x09";
+
+#[test]
+fn until_repeat_round_trip() {
+    let parsed = UntilRepeat::read(&mut &DATA[..]).unwrap();
+
+    // sentinels are kept as the last element
+    assert_eq!(parsed.values, vec![5, 7, 0]);
+    assert_eq!(
+        parsed.records,
+        vec![record { id: 1, score: 10 }, record { id: 255, score: 0 }]
+    );
+    assert_eq!(parsed.last, 9);
+
+    let mut written = Vec::new();
+    parsed.write(&mut written).unwrap();
+    assert_eq!(written, DATA);
+}
+
+#[test]
+fn until_repeat_missing_sentinel() {
+    let mut value = UntilRepeat::read(&mut &DATA[..]).unwrap();
+    value.values.pop();
+    assert!(value.write(&mut Vec::new()).is_err());
+
+    value.values = vec![0, 5, 0];
+    assert!(value.write(&mut Vec::new()).is_err());
+
+    value.values = vec![0];
+    value.marker = 1;
+    assert!(value.write(&mut Vec::new()).is_err());
+}
+
+#[test]
+fn until_repeat_size_hint() {
+    assert_eq!(UntilRepeat::size_hint(), (7, None));
+}
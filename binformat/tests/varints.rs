@@ -0,0 +1,48 @@
+use binformat::format_source;
+use std::io::ErrorKind;
+
+#[format_source("tests/formats/varints.format")]
+pub struct Varints;
+
+const DATA: &[u8] = b"\x04\x00\x3f\x40\xc0\xbb\x78\xe5\x8e\x26";
+
+#[test]
+fn read_varints() {
+    let value = Varints::read(&mut &DATA[..]).unwrap();
+
+    assert_eq!(value.count, 4);
+    assert_eq!(value.values, [0, 63, -64, -123456]);
+    assert_eq!(value.large, 624485);
+}
+
+#[test]
+fn varints_roundtrip() {
+    let value = Varints::read(&mut &DATA[..]).unwrap();
+    assert_eq!(value.to_bytes().unwrap(), DATA);
+
+    let extremes = Varints {
+        count: 2,
+        values: vec![i64::MIN, i64::MAX],
+        large: u64::MAX,
+    };
+    let bytes = extremes.to_bytes().unwrap();
+    assert_eq!(bytes.len(), 1 + 10 + 10 + 10);
+    assert_eq!(Varints::read(&mut bytes.as_slice()).unwrap(), extremes);
+}
+
+#[test]
+fn overlong_varints_are_rejected() {
+    let too_large: &[u8] = b"\x00\xff\xff\xff\xff\xff\xff\xff\xff\xff\x02";
+    // a tenth byte continuing on, even with nothing in it, would shift past 64 bits
+    let too_long: &[u8] = b"\x00\xff\xff\xff\xff\xff\xff\xff\xff\xff\x80\x00";
+
+    for data in [too_large, too_long] {
+        let error = Varints::read(&mut &data[..]).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn size_hint_covers_every_length() {
+    assert_eq!(Varints::size_hint(), (2, None));
+}
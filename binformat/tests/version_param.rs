@@ -0,0 +1,47 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/version_param.format")]
+pub struct VersionParam;
+
+const VERSION_1: [u8; 8] = [100, 0, 0, 0, 1, 0, 2, 0];
+const VERSION_3: [u8; 12] = [100, 0, 0, 0, 50, 0, 1, 0, 4, 2, 0, 5];
+
+#[test]
+fn fields_are_gated_on_the_version() {
+    let old = VersionParam::read(&mut &VERSION_1[..], 1).unwrap();
+    let new = VersionParam::read(&mut &VERSION_3[..], 3).unwrap();
+
+    assert_eq!(old.gems, None);
+    assert_eq!(old.buildings[0].upgrades, None);
+    assert_eq!(new.gems, Some(50));
+    assert_eq!(
+        new.buildings,
+        vec![
+            building {
+                count: 1,
+                upgrades: Some(4)
+            },
+            building {
+                count: 2,
+                upgrades: Some(5)
+            },
+        ]
+    );
+
+    assert_eq!(old.to_bytes().unwrap(), VERSION_1);
+    assert_eq!(new.to_bytes().unwrap(), VERSION_3);
+}
+
+#[test]
+fn version_is_passed_through() {
+    assert_eq!(
+        VersionParam::from_bytes(&VERSION_3, 3).unwrap(),
+        VersionParam::read(&mut &VERSION_3[..], 3).unwrap()
+    );
+    assert_eq!(
+        VersionParam::read_counted(&mut &VERSION_1[..], 1)
+            .unwrap()
+            .1,
+        VERSION_1.len()
+    );
+}
@@ -0,0 +1,22 @@
+use binformat::format_source;
+
+#[format_source("tests/formats/wide_integers.format")]
+pub struct WideIntegers;
+
+#[test]
+fn wide_integers_roundtrip() {
+    let value = WideIntegers {
+        mask: 1 << 127 | 1,
+        signed: -2,
+    };
+
+    let bytes = value.to_bytes().unwrap();
+    assert_eq!(
+        bytes[..16],
+        [0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+    );
+    // byteswapped back to little endian
+    assert_eq!(bytes[16], 0xfe);
+    assert_eq!(WideIntegers::read(&mut bytes.as_slice()).unwrap(), value);
+    assert_eq!(WideIntegers::size_hint(), (32, Some(32)));
+}
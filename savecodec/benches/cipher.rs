@@ -1,6 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-
-const CIPHER_KEY: &[u8] = b"therealmisalie";
+use savecodec::{stages, DEFAULT_CIPHER_KEY as CIPHER_KEY};
 
 fn for_loop(out: &mut [u8]) {
     for (index, byte) in out.iter_mut().enumerate() {
@@ -15,12 +14,23 @@ fn functional(out: &mut [u8]) {
 }
 
 fn bench(c: &mut Criterion) {
-    let save = std::fs::read_to_string("save.txt").unwrap();
+    let save = std::fs::read_to_string("../save.txt").unwrap();
     let mut data = save.into_bytes();
 
     let mut group = c.benchmark_group("Cipher");
-    group.bench_function("for loop", |b| b.iter(|| for_loop(&mut data)));
-    group.bench_function("functional", |b| b.iter(|| functional(&mut data)));
+    group.bench_function("for loop", |b| b.iter(|| for_loop(black_box(&mut data))));
+    group.bench_function("functional", |b| {
+        b.iter(|| functional(black_box(&mut data)))
+    });
+    // the crate applies the key a table at a time, with SIMD if the feature is enabled
+    let name = if cfg!(feature = "simd") {
+        "simd"
+    } else {
+        "table"
+    };
+    group.bench_function(name, |b| {
+        b.iter(|| stages::apply_cipher(black_box(&mut data)))
+    });
 
     group.finish();
 }
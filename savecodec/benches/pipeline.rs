@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use savecodec::{decode_to_raw, encode_from_raw, stages};
+
+/// Extracts the base64 payload from between the save header and footer
+fn payload(save: &str) -> &[u8] {
+    let save = save.trim();
+    &save.as_bytes()[4..save.len() - 2]
+}
+
+fn decode(c: &mut Criterion) {
+    let save = std::fs::read_to_string("../save.txt").unwrap();
+    let compressed = stages::decode_base64(payload(&save)).unwrap();
+    let inflated = stages::inflate(&compressed).unwrap();
+
+    let mut group = c.benchmark_group("Decode");
+    group.bench_function("full", |b| b.iter(|| decode_to_raw(black_box(&save))));
+    group.bench_function("base64", |b| {
+        b.iter(|| stages::decode_base64(black_box(payload(&save))))
+    });
+    group.bench_function("inflate", |b| {
+        b.iter(|| stages::inflate(black_box(&compressed)))
+    });
+    group.bench_function("cipher", |b| {
+        b.iter_batched_ref(
+            || inflated.clone(),
+            |data| stages::apply_cipher(black_box(data)),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+
+    group.finish();
+}
+
+fn encode(c: &mut Criterion) {
+    let save = std::fs::read_to_string("../save.txt").unwrap();
+    let raw = decode_to_raw(&save).unwrap();
+    let mut enciphered = raw.clone();
+    stages::apply_cipher(&mut enciphered);
+    let compressed = stages::deflate(&enciphered).unwrap();
+
+    let mut group = c.benchmark_group("Encode");
+    group.bench_function("full", |b| b.iter(|| encode_from_raw(black_box(&raw), 55)));
+    group.bench_function("cipher", |b| {
+        b.iter_batched_ref(
+            || raw.clone(),
+            |data| stages::apply_cipher(black_box(data)),
+            criterion::BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("deflate", |b| {
+        b.iter(|| stages::deflate(black_box(&enciphered)))
+    });
+    group.bench_function("base64", |b| {
+        b.iter(|| stages::encode_base64(black_box(&compressed)))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, decode, encode);
+criterion_main!(benches);
@@ -1,33 +1,416 @@
-#![feature(const_for)]
 #![allow(overflowing_literals)]
+#![cfg_attr(feature = "simd", feature(portable_simd))]
 
 use binformat::format_source;
 use flate2::{
-    read::{ZlibDecoder, ZlibEncoder},
+    read::{GzDecoder, GzEncoder, ZlibDecoder, ZlibEncoder},
     Compression,
 };
 use lazy_static::lazy_static;
-use regex::Regex;
-use std::io::Read;
+use regex::bytes::Regex;
+use std::{
+    io::{Cursor, Read},
+    time::{Duration, Instant},
+};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum SaveError {
     #[error("save string not in a known format")]
     InvalidSaveString,
-    #[error("save data not valid base64")]
-    InvalidBase64,
+    #[error("save data not valid base64 at byte {position}")]
+    InvalidBase64 { position: usize },
+    #[error("base64 alphabet must be 64 distinct ascii characters, excluding `=`")]
+    InvalidAlphabet,
     #[error("save data compression error")]
     CompressError(#[from] std::io::Error),
     #[error("binary read/write error")]
     RWError(std::io::Error),
+    #[error("save version {0} isn't two digits")]
+    InvalidVersion(String),
+    #[error("save json not valid")]
+    InvalidJson(serde_json::Error),
+    #[error("cipher key must not be empty")]
+    EmptyKey,
+    #[error("compression level {0} not in range 0..=9")]
+    InvalidCompressionLevel(u32),
+    #[error("encoded save doesn't decode back to the same data")]
+    RoundtripMismatch,
+}
+
+/// Error from parsing a save with [`Save::parse_str`] or [`Save::parse_str_checked`], giving the stage of
+/// parsing which failed
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("save string not in a known format")]
+    Envelope,
+    #[error("save data couldn't be decoded")]
+    Decode(#[source] SaveError),
+    #[error("save field `{field}` couldn't be read at byte {offset}")]
+    StructParse { field: &'static str, offset: usize },
+    #[error("save version {found} doesn't match expected version {expected}")]
+    VersionMismatch { expected: u16, found: u16 },
+}
+
+impl From<SaveError> for ParseError {
+    fn from(error: SaveError) -> Self {
+        match error {
+            SaveError::InvalidSaveString => ParseError::Envelope,
+            error => ParseError::Decode(error),
+        }
+    }
 }
 
 /// Key for the vigenere cipher
-const CIPHER_KEY: &[u8] = b"therealmisalie";
+pub const DEFAULT_CIPHER_KEY: &[u8] = b"therealmisalie";
+
+/// Number of bytes XORed at once with the `simd` feature
+#[cfg(feature = "simd")]
+const CIPHER_LANES: usize = 32;
+
+/// Length of [`DEFAULT_CIPHER_TABLE`]. The key (14 bytes) doesn't divide evenly into SIMD vectors, so this
+/// is the shortest length which is a whole number of both keys and vectors - letting each vector of the
+/// table line up with the key no matter where it starts.
+const CIPHER_TABLE_LEN: usize = 224;
+
+/// The default key repeated, so the cipher can be applied a table at a time
+const DEFAULT_CIPHER_TABLE: [u8; CIPHER_TABLE_LEN] = cipher_table(DEFAULT_CIPHER_KEY);
+
+/// Zlib compression level used when encoding saves
+pub const DEFAULT_COMPRESSION_LEVEL: u32 = 6;
+
+/// Symbols of the standard base64 alphabet, in order
+const STANDARD_SYMBOLS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Symbols of the URL safe base64 alphabet, in order
+const URL_SAFE_SYMBOLS: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// A base64 alphabet, for saves from builds which don't use the standard one.
+///
+/// # Example
+/// ```
+/// # use savecodec::Alphabet;
+/// assert!(Alphabet::new("ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_").is_ok());
+/// assert!(Alphabet::new("too short").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    symbols: [u8; 64],
+}
+
+impl Alphabet {
+    /// Creates an alphabet from its 64 symbols, in the order of the values they encode
+    pub fn new(symbols: &str) -> Result<Self, SaveError> {
+        let symbols: [u8; 64] = symbols
+            .as_bytes()
+            .try_into()
+            .or(Err(SaveError::InvalidAlphabet))?;
+
+        // symbols need to be distinct to decode, and `=` is kept for padding
+        let valid = symbols.iter().enumerate().all(|(index, symbol)| {
+            symbol.is_ascii() && *symbol != b'=' && !symbols[..index].contains(symbol)
+        });
+        if !valid {
+            return Err(SaveError::InvalidAlphabet);
+        }
+
+        Ok(Self { symbols })
+    }
+
+    /// The URL safe base64 alphabet, using `-` and `_` in place of `+` and `/` so saves can be put in
+    /// URLs without escaping
+    pub fn url_safe() -> Self {
+        Self {
+            symbols: *URL_SAFE_SYMBOLS,
+        }
+    }
+
+    /// Detects whether a save uses the URL safe or standard alphabet
+    fn detect(save: &[u8]) -> Self {
+        // only the URL safe alphabet has these symbols, and the rest of the save string doesn't use them
+        if save.iter().any(|byte| matches!(byte, b'-' | b'_')) {
+            Self::url_safe()
+        } else {
+            Self::default()
+        }
+    }
+
+    /// Gets the base64 config for this alphabet, if it's one which base64 supports directly
+    fn config(&self) -> Option<base64::Config> {
+        match &self.symbols {
+            STANDARD_SYMBOLS => Some(base64::STANDARD),
+            URL_SAFE_SYMBOLS => Some(base64::URL_SAFE),
+            _ => None,
+        }
+    }
+
+    /// Decodes base64 data written with this alphabet
+    fn decode(&self, data: &[u8]) -> Result<Vec<u8>, SaveError> {
+        if let Some(config) = self.config() {
+            return base64::decode_config(data, config)
+                .map_err(|error| base64_error(error, data.len()));
+        }
+
+        // translate into the standard alphabet, leaving padding as is
+        let standard = data
+            .iter()
+            .enumerate()
+            .map(
+                |(position, &byte)| match self.symbols.iter().position(|symbol| *symbol == byte) {
+                    Some(index) => Ok(STANDARD_SYMBOLS[index]),
+                    None if byte == b'=' => Ok(byte),
+                    None => Err(SaveError::InvalidBase64 { position }),
+                },
+            )
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // symbols are translated one to one, so positions in errors still line up with the save
+        base64::decode(standard).map_err(|error| base64_error(error, data.len()))
+    }
+
+    /// Encodes data as base64 with this alphabet
+    fn encode(&self, data: &[u8]) -> String {
+        if let Some(config) = self.config() {
+            return base64::encode_config(data, config);
+        }
+
+        let standard = base64::encode(data);
+
+        standard
+            .bytes()
+            .map(
+                |byte| match STANDARD_SYMBOLS.iter().position(|symbol| *symbol == byte) {
+                    Some(index) => self.symbols[index] as char,
+                    None => byte as char,
+                },
+            )
+            .collect()
+    }
+}
+
+impl Default for Alphabet {
+    /// The standard base64 alphabet
+    fn default() -> Self {
+        Self {
+            symbols: *STANDARD_SYMBOLS,
+        }
+    }
+}
+
+/// Sizes and timings of each stage of decoding a save, useful for monitoring save ingestion.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodeReport {
+    /// Version from the save string
+    pub version: u16,
+    /// Length of the data after base64 decoding
+    pub base64_len: usize,
+    /// Length of the data after inflating
+    pub decompressed_len: usize,
+    /// Ratio of decompressed length to compressed length
+    pub compression_ratio: f64,
+    /// Time spent extracting and base64 decoding the data
+    pub base64_time: Duration,
+    /// Time spent inflating the data
+    pub inflate_time: Duration,
+    /// Time spent applying the cipher
+    pub cipher_time: Duration,
+}
+
+/// Converts a base64 error into a save error, keeping the position in the save data it happened at.
+/// Errors with the length of the data have no single position, so are given the length instead.
+fn base64_error(error: base64::DecodeError, len: usize) -> SaveError {
+    let position = match error {
+        base64::DecodeError::InvalidByte(position, _)
+        | base64::DecodeError::InvalidLastSymbol(position, _) => position,
+        base64::DecodeError::InvalidLength => len,
+    };
+
+    SaveError::InvalidBase64 { position }
+}
+
+/// Extracts the save version and base64 decoded save data from a save string, given as bytes so it
+/// doesn't have to be valid UTF-8. Whitespace around the save (such as a trailing newline when copied
+/// from a text box) is ignored.
+fn extract(save: &[u8], alphabet: &Alphabet) -> Result<(u16, Vec<u8>), SaveError> {
+    lazy_static! {
+        /// Regex to extract save version (first group) and save data (second group) from the string
+        static ref SAVE_REGEX: Regex = Regex::new(r"(?-u)^\$([^$]{2})s(.*)\$e$").unwrap();
+    }
+
+    let captures = SAVE_REGEX
+        .captures(save.trim_ascii())
+        .ok_or(SaveError::InvalidSaveString)?;
+    let version = &captures[1];
+    if !version.iter().all(u8::is_ascii_digit) {
+        return Err(SaveError::InvalidVersion(
+            String::from_utf8_lossy(version).into_owned(),
+        ));
+    }
+    let version = version
+        .iter()
+        .fold(0, |version, digit| version * 10 + u16::from(digit - b'0'));
+    let data = alphabet.decode(&captures[2])?;
+
+    Ok((version, data))
+}
+
+/// Magic bytes at the start of gzip compressed data, which some builds use instead of zlib
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+/// Compression format of the data inside a save string
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Container {
+    /// Zlib compression, which the game itself writes
+    #[default]
+    Zlib,
+    /// Gzip compression, used by some other builds
+    Gzip,
+}
+
+impl Container {
+    /// Detects the container of compressed data from its first bytes, falling back to zlib
+    fn detect(data: &[u8]) -> Self {
+        if data.starts_with(GZIP_MAGIC) {
+            Container::Gzip
+        } else {
+            Container::Zlib
+        }
+    }
+}
+
+/// Inflates compressed data from the given container
+fn inflate(data: &[u8], container: Container) -> Result<Vec<u8>, SaveError> {
+    let mut out = Vec::new();
+    match container {
+        Container::Zlib => ZlibDecoder::new(data).read_to_end(&mut out),
+        Container::Gzip => GzDecoder::new(data).read_to_end(&mut out),
+    }
+    .map_err(SaveError::CompressError)?;
+
+    Ok(out)
+}
+
+/// Deflates data into the given container
+fn deflate(data: &[u8], container: Container, level: u32) -> Result<Vec<u8>, SaveError> {
+    let mut out = Vec::new();
+    match container {
+        Container::Zlib => ZlibEncoder::new(data, Compression::new(level)).read_to_end(&mut out),
+        Container::Gzip => GzEncoder::new(data, Compression::new(level)).read_to_end(&mut out),
+    }
+    .map_err(SaveError::CompressError)?;
+
+    Ok(out)
+}
+
+/// Checks if data starts with a zlib or gzip header using deflate, without inflating anything
+fn has_compression_header(data: &[u8]) -> bool {
+    match data {
+        // gzip's third byte gives the compression method, which is always deflate (8)
+        [0x1f, 0x8b, 8, ..] => true,
+        // zlib's first byte gives the method and window size, with the first two bytes a multiple of 31
+        [cmf, flg, ..] => {
+            cmf & 0x0f == 8 && cmf >> 4 <= 7 && (u16::from(*cmf) << 8 | u16::from(*flg)) % 31 == 0
+        }
+        _ => false,
+    }
+}
+
+/// Cheaply checks if a string looks like a valid save - that it's wrapped correctly, its data is
+/// valid base64 and starts with a compression header - without inflating or deciphering it.
+///
+/// A save passing this can still fail to decode if its compressed data is corrupt, but anything that
+/// fails this would also fail to decode.
+///
+/// # Example
+/// ```
+/// # use savecodec::is_valid_save;
+/// assert!(is_valid_save("$00seJwrLi0GAAK5AVw=$e"));
+/// assert!(!is_valid_save("$00seJwrLi0GAAK5AVw=")); // missing the end of the wrapper
+/// assert!(!is_valid_save(""));
+/// ```
+pub fn is_valid_save(save: &str) -> bool {
+    extract(save.as_bytes(), &Alphabet::detect(save.as_bytes()))
+        .is_ok_and(|(_, data)| has_compression_header(&data))
+}
+
+/// Builds a table of the key repeated to fill `N` bytes, for applying the cipher without cycling through
+/// the key for every byte. `N` has to be a whole number of keys, so that consecutive tables line up.
+///
+/// # Example
+/// ```
+/// # use savecodec::cipher_table;
+/// const TABLE: [u8; 6] = cipher_table(b"key");
+/// assert_eq!(&TABLE, b"keykey");
+/// ```
+pub const fn cipher_table<const N: usize>(key: &[u8]) -> [u8; N] {
+    assert!(
+        !key.is_empty() && N.is_multiple_of(key.len()),
+        "cipher tables must be a whole number of keys"
+    );
+
+    let mut table = [0; N];
+    let mut index = 0;
+    while index < N {
+        table[index] = key[index % key.len()];
+        index += 1;
+    }
+
+    table
+}
+
+/// Applies the default key's table to data no longer than it, starting from the start of the key
+fn apply_cipher_table(data: &mut [u8]) {
+    data.iter_mut()
+        .zip(&DEFAULT_CIPHER_TABLE)
+        .for_each(|(byte, key)| *byte ^= key);
+}
+
+/// Applies the vigenere cipher with the default key, a table at a time
+#[cfg(not(feature = "simd"))]
+fn apply_default_cipher(data: &mut [u8]) {
+    data.chunks_mut(CIPHER_TABLE_LEN)
+        .for_each(apply_cipher_table);
+}
+
+/// Applies the vigenere cipher with the default key, XORing a vector of the table at a time. Whatever's
+/// left after the last whole table is applied without SIMD.
+#[cfg(feature = "simd")]
+fn apply_default_cipher(data: &mut [u8]) {
+    use std::simd::Simd;
+
+    let mut tables = data.chunks_exact_mut(CIPHER_TABLE_LEN);
+    for table in &mut tables {
+        let keys = DEFAULT_CIPHER_TABLE.chunks_exact(CIPHER_LANES);
+        for (lanes, key) in table.chunks_exact_mut(CIPHER_LANES).zip(keys) {
+            let key = Simd::<u8, CIPHER_LANES>::from_slice(key);
+            (Simd::from_slice(lanes) ^ key).copy_to_slice(lanes);
+        }
+    }
+
+    apply_cipher_table(tables.into_remainder());
+}
+
+/// Applies the vigenere cipher in place, which is its own inverse
+fn apply_cipher(data: &mut [u8], key: &[u8]) {
+    // the default key has a table built ahead of time, which is much faster to apply
+    if key == DEFAULT_CIPHER_KEY {
+        apply_default_cipher(data);
+        return;
+    }
+
+    data.iter_mut()
+        .zip(key.iter().cycle())
+        .for_each(|(byte, key)| *byte ^= key);
+}
 
 /// Decodes a save into raw binary data which can then be parsed.
 ///
+/// The data can be compressed with either zlib (as written by [`encode_from_raw`]) or gzip, which is
+/// detected automatically. Saves using the URL safe base64 alphabet (as written by
+/// [`encode_from_raw_url_safe`]) are detected too.
+///
 /// # Example
 /// ```
 /// # use savecodec::decode_to_raw;
@@ -37,77 +420,1113 @@ const CIPHER_KEY: &[u8] = b"therealmisalie";
 /// assert!(decode_to_raw(&save).is_ok());
 /// ```
 pub fn decode_to_raw(save: &str) -> Result<Vec<u8>, SaveError> {
-    lazy_static! {
-        /// Regex to extract save version (first group) and save data (second group) from the string
-        static ref SAVE_REGEX: Regex = Regex::new(r"^\$([0-9]{2})s(.*)\$e$").unwrap();
+    decode_to_raw_bytes(save.as_bytes())
+}
+
+/// Decodes a save like [`decode_to_raw`], from bytes such as a file's contents - skipping the UTF-8
+/// validation needed to make a `&str` of it first.
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_raw, decode_to_raw_bytes};
+/// assert_eq!(decode_to_raw_bytes(b"$00seJwrLi0GAAK5AVw=$e").unwrap(), vec![7, 29, 22]);
+///
+/// let save = std::fs::read("../save.txt").unwrap();
+/// assert_eq!(decode_to_raw_bytes(&save).unwrap(), decode_to_raw(&String::from_utf8(save).unwrap()).unwrap());
+/// ```
+pub fn decode_to_raw_bytes(save: &[u8]) -> Result<Vec<u8>, SaveError> {
+    decode(save, &Alphabet::detect(save), DEFAULT_CIPHER_KEY).map(|(_, data)| data)
+}
+
+/// Decodes a save like [`decode_to_raw`], for saves base64 encoded with the URL safe alphabet.
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_raw, decode_to_raw_url_safe, encode_from_raw_url_safe};
+/// let data = vec![251, 255, 190];
+/// let save = encode_from_raw_url_safe(&data, 0).unwrap();
+///
+/// assert_eq!(decode_to_raw_url_safe(&save).unwrap(), data);
+/// assert_eq!(decode_to_raw(&save).unwrap(), data);
+/// ```
+pub fn decode_to_raw_url_safe(save: &str) -> Result<Vec<u8>, SaveError> {
+    decode_to_raw_with_alphabet(save, &Alphabet::url_safe())
+}
+
+/// Reader over a save's compressed data, deciphering bytes as they're inflated
+struct DecodeReader {
+    inner: Box<dyn Read>,
+    key: &'static [u8],
+    /// Position in the key of the next byte read
+    offset: usize,
+}
+
+impl Read for DecodeReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+
+        // the key carries on from where the last read stopped, rather than restarting each read
+        buf[..read]
+            .iter_mut()
+            .zip(self.key.iter().cycle().skip(self.offset))
+            .for_each(|(byte, key)| *byte ^= key);
+        self.offset = (self.offset + read) % self.key.len();
+
+        Ok(read)
     }
+}
+
+/// Decodes a save like [`decode_to_raw`], but gives a reader which inflates and deciphers the data as
+/// it's read instead of all at once. This is useful for large saves when only the start is needed.
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_raw, decode_to_reader};
+/// use byteorder::{LittleEndian, ReadBytesExt};
+///
+/// let save = std::fs::read_to_string("../save.txt").unwrap();
+/// let mut reader = decode_to_reader(&save).unwrap();
+///
+/// let raw = decode_to_raw(&save).unwrap();
+/// assert_eq!(reader.read_u16::<LittleEndian>().unwrap(), u16::from_le_bytes([raw[0], raw[1]]));
+/// ```
+pub fn decode_to_reader(save: &str) -> Result<impl Read, SaveError> {
+    let (_, data) = extract(save.as_bytes(), &Alphabet::detect(save.as_bytes()))?;
+
+    let inner: Box<dyn Read> = match Container::detect(&data) {
+        Container::Zlib => Box::new(ZlibDecoder::new(Cursor::new(data))),
+        Container::Gzip => Box::new(GzDecoder::new(Cursor::new(data))),
+    };
+
+    Ok(DecodeReader {
+        inner,
+        key: DEFAULT_CIPHER_KEY,
+        offset: 0,
+    })
+}
+
+/// Decodes a save like [`decode_to_raw`], also giving the version from the start of the save string so
+/// the layout of the data can be chosen before parsing it.
+///
+/// The version is always two digits, so strings with anything else there fail with
+/// [`SaveError::InvalidVersion`].
+///
+/// # Example
+/// ```
+/// # use savecodec::decode_to_raw_versioned;
+/// assert_eq!(decode_to_raw_versioned("$00seJwrLi0GAAK5AVw=$e").unwrap(), (0, vec![7, 29, 22]));
+/// ```
+pub fn decode_to_raw_versioned(save: &str) -> Result<(u16, Vec<u8>), SaveError> {
+    decode(
+        save.as_bytes(),
+        &Alphabet::detect(save.as_bytes()),
+        DEFAULT_CIPHER_KEY,
+    )
+}
 
+/// Decodes a save like [`decode_to_raw`], for saves base64 encoded with a custom alphabet.
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_raw_with_alphabet, Alphabet};
+/// let alphabet = Alphabet::new("BCDEFGHIJKLMNOPQRSTUVWXYZAbcdefghijklmnopqrstuvwxyza1234567890+/").unwrap();
+/// assert_eq!(decode_to_raw_with_alphabet("$00sfKxsMj1HBBL6BWx=$e", &alphabet).unwrap(), vec![7, 29, 22]);
+/// ```
+pub fn decode_to_raw_with_alphabet(save: &str, alphabet: &Alphabet) -> Result<Vec<u8>, SaveError> {
+    decode(save.as_bytes(), alphabet, DEFAULT_CIPHER_KEY).map(|(_, data)| data)
+}
+
+/// Decodes a save like [`decode_to_raw`], for saves enciphered with a different vigenere key.
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_raw_with_key, encode_from_raw_with_key, SaveError};
+/// let save = encode_from_raw_with_key(&[7, 29, 22], 0, b"key").unwrap();
+/// assert_eq!(decode_to_raw_with_key(&save, b"key").unwrap(), vec![7, 29, 22]);
+///
+/// assert!(matches!(decode_to_raw_with_key(&save, b""), Err(SaveError::EmptyKey)));
+/// ```
+pub fn decode_to_raw_with_key(save: &str, key: &[u8]) -> Result<Vec<u8>, SaveError> {
+    if key.is_empty() {
+        return Err(SaveError::EmptyKey);
+    }
+
+    decode(save.as_bytes(), &Alphabet::detect(save.as_bytes()), key).map(|(_, data)| data)
+}
+
+/// Decodes a save like [`decode_to_raw`], only accepting data compressed in the given container rather
+/// than detecting it.
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_raw, decode_to_raw_with_container, encode_from_raw_with_container, Container};
+/// let save = encode_from_raw_with_container(&[7, 29, 22], 0, Container::Gzip).unwrap();
+/// assert_eq!(decode_to_raw_with_container(&save, Container::Gzip).unwrap(), vec![7, 29, 22]);
+/// assert!(decode_to_raw_with_container(&save, Container::Zlib).is_err());
+///
+/// // containers are detected automatically otherwise
+/// assert_eq!(decode_to_raw(&save).unwrap(), vec![7, 29, 22]);
+/// ```
+pub fn decode_to_raw_with_container(
+    save: &str,
+    container: Container,
+) -> Result<Vec<u8>, SaveError> {
+    let (_, data) = extract(save.as_bytes(), &Alphabet::detect(save.as_bytes()))?;
+    let mut out = inflate(&data, container)?;
+
+    apply_cipher(&mut out, DEFAULT_CIPHER_KEY);
+    Ok(out)
+}
+
+/// Decodes a batch of saves in parallel, giving the result for each save in the same order as the input.
+/// A save failing to decode doesn't affect the rest of the batch.
+///
+/// # Example
+/// ```
+/// # use savecodec::decode_archive;
+/// let saves = ["$00seJwrLi0GAAK5AVw=$e".to_owned(), "not a save".to_owned()];
+/// let decoded = decode_archive(&saves);
+///
+/// assert_eq!(decoded[0].as_ref().unwrap(), &vec![7, 29, 22]);
+/// assert!(decoded[1].is_err());
+/// ```
+#[cfg(feature = "rayon")]
+pub fn decode_archive(saves: &[String]) -> Vec<Result<Vec<u8>, SaveError>> {
+    use rayon::prelude::*;
+
+    saves.par_iter().map(|save| decode_to_raw(save)).collect()
+}
+
+/// Decodes a save into its version and inflated data, which is still enciphered
+fn decode_inflated(save: &[u8], alphabet: &Alphabet) -> Result<(u16, Vec<u8>), SaveError> {
     // extract save data from save string, and then decode to byte array
-    let data = &SAVE_REGEX
-        .captures(save)
-        .ok_or(SaveError::InvalidSaveString)?[2];
-    let data = base64::decode(data).or(Err(SaveError::InvalidBase64))?;
+    let (version, data) = extract(save, alphabet)?;
 
     // then inflate with zlib
-    let mut decoder = ZlibDecoder::new(&data[..]);
+    let out = inflate(&data, Container::detect(&data))?;
+    Ok((version, out))
+}
+
+/// Decodes a save into its version and raw binary data
+fn decode(save: &[u8], alphabet: &Alphabet, key: &[u8]) -> Result<(u16, Vec<u8>), SaveError> {
+    let (version, mut out) = decode_inflated(save, alphabet)?;
+
+    // finally apply vigenere cipher with the key to get the raw save data in a usable form
+    apply_cipher(&mut out, key);
+    Ok((version, out))
+}
+
+/// Decodes a save like [`decode_to_raw`], but stops after inflating - leaving the data enciphered, for
+/// comparing against other tools. [`encode_from_inflated`] does the opposite.
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_inflated, decode_to_raw, DEFAULT_CIPHER_KEY};
+/// let save = std::fs::read_to_string("../save.txt").unwrap();
+/// let inflated = decode_to_inflated(&save).unwrap();
+///
+/// let deciphered: Vec<u8> = inflated
+///     .iter()
+///     .zip(DEFAULT_CIPHER_KEY.iter().cycle())
+///     .map(|(byte, key)| byte ^ key)
+///     .collect();
+/// assert_eq!(deciphered, decode_to_raw(&save).unwrap());
+/// ```
+pub fn decode_to_inflated(save: &str) -> Result<Vec<u8>, SaveError> {
+    decode_inflated(save.as_bytes(), &Alphabet::detect(save.as_bytes())).map(|(_, data)| data)
+}
+
+/// Decodes a save into raw binary data like [`decode_to_raw`], also returning any bytes which follow
+/// the zlib stream (such as an unencrypted suffix some builds append) untouched.
+///
+/// # Example
+/// ```
+/// # use savecodec::decode_with_trailer;
+/// let mut data = base64::decode("eJwrLi0GAAK5AVw=").unwrap();
+/// data.extend_from_slice(b"tail");
+/// let save = format!("$00s{}$e", base64::encode(&data));
+///
+/// assert_eq!(decode_with_trailer(&save).unwrap(), (vec![7, 29, 22], b"tail".to_vec()));
+/// ```
+pub fn decode_with_trailer(save: &str) -> Result<(Vec<u8>, Vec<u8>), SaveError> {
+    let (_, data) = extract(save.as_bytes(), &Alphabet::detect(save.as_bytes()))?;
+
+    // buffered decoders only consume the compressed stream itself, leaving the trailer in the slice
     let mut out = Vec::new();
-    decoder
-        .read_to_end(&mut out)
-        .map_err(SaveError::CompressError)?;
+    let trailer = match Container::detect(&data) {
+        Container::Zlib => {
+            let mut decoder = flate2::bufread::ZlibDecoder::new(&data[..]);
+            decoder
+                .read_to_end(&mut out)
+                .map_err(SaveError::CompressError)?;
+            decoder.into_inner()
+        }
+        Container::Gzip => {
+            let mut decoder = flate2::bufread::GzDecoder::new(&data[..]);
+            decoder
+                .read_to_end(&mut out)
+                .map_err(SaveError::CompressError)?;
+            decoder.into_inner()
+        }
+    }
+    .to_vec();
 
-    // finally apply vigenere cipher with known key to get the raw save data in a usable form
-    out.iter_mut()
-        .zip(CIPHER_KEY.iter().cycle())
-        .for_each(|(byte, key)| *byte ^= key);
-    Ok(out)
+    apply_cipher(&mut out, DEFAULT_CIPHER_KEY);
+    Ok((out, trailer))
+}
+
+/// Decodes a save into raw binary data like [`decode_to_raw`], also reporting the size and duration of each stage.
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_raw, decode_with_report};
+/// let save = std::fs::read_to_string("../save.txt").unwrap();
+/// let (raw, report) = decode_with_report(&save).unwrap();
+///
+/// assert_eq!(raw, decode_to_raw(&save).unwrap());
+/// assert_eq!(report.version, 0);
+/// assert_eq!(report.decompressed_len, raw.len());
+/// assert_eq!(report.base64_len, base64::decode(&save[4..save.len() - 2]).unwrap().len());
+/// assert_eq!(report.compression_ratio, raw.len() as f64 / report.base64_len as f64);
+/// ```
+pub fn decode_with_report(save: &str) -> Result<(Vec<u8>, DecodeReport), SaveError> {
+    let start = Instant::now();
+    let (version, data) = extract(save.as_bytes(), &Alphabet::detect(save.as_bytes()))?;
+    let base64_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut out = inflate(&data, Container::detect(&data))?;
+    let inflate_time = start.elapsed();
+
+    let start = Instant::now();
+    apply_cipher(&mut out, DEFAULT_CIPHER_KEY);
+    let cipher_time = start.elapsed();
+
+    let report = DecodeReport {
+        version,
+        base64_len: data.len(),
+        decompressed_len: out.len(),
+        compression_ratio: out.len() as f64 / data.len() as f64,
+        base64_time,
+        inflate_time,
+        cipher_time,
+    };
+
+    Ok((out, report))
 }
 
 /// Encodes raw binary data into an RG save
 ///
+/// # Determinism
+/// Encoding the same data always gives the same string with a given build, but the exact bytes
+/// come from flate2's zlib compressor and so may change when flate2 (or its backend) is updated.
+/// Any such output is still decoded correctly, so only compare encoded saves byte-for-byte
+/// when they were produced by the same flate2 version.
+///
 /// # Example
 /// ```
 /// # use savecodec::encode_from_raw;
 /// assert_eq!(encode_from_raw(&[7, 29, 22], 0).unwrap(), "$00seJwrLi0GAAK5AVw=$e");
 /// ```
 pub fn encode_from_raw(data: &[u8], version: u16) -> Result<String, SaveError> {
+    encode_from_raw_with_alphabet(data, version, &Alphabet::default())
+}
+
+/// Encodes raw binary data into an RG save like [`encode_from_raw`], base64 encoding with a custom alphabet.
+///
+/// # Example
+/// ```
+/// # use savecodec::{encode_from_raw_with_alphabet, Alphabet};
+/// let alphabet = Alphabet::new("BCDEFGHIJKLMNOPQRSTUVWXYZAbcdefghijklmnopqrstuvwxyza1234567890+/").unwrap();
+/// assert_eq!(encode_from_raw_with_alphabet(&[7, 29, 22], 0, &alphabet).unwrap(), "$00sfKxsMj1HBBL6BWx=$e");
+/// ```
+pub fn encode_from_raw_with_alphabet(
+    data: &[u8],
+    version: u16,
+    alphabet: &Alphabet,
+) -> Result<String, SaveError> {
+    encode(
+        data,
+        version,
+        alphabet,
+        DEFAULT_CIPHER_KEY,
+        Container::Zlib,
+        DEFAULT_COMPRESSION_LEVEL,
+    )
+}
+
+/// Encodes raw binary data into an RG save like [`encode_from_raw`], base64 encoding with the URL safe
+/// alphabet so the save can be put in a URL. See [`decode_to_raw_url_safe`] for an example.
+pub fn encode_from_raw_url_safe(data: &[u8], version: u16) -> Result<String, SaveError> {
+    encode_from_raw_with_alphabet(data, version, &Alphabet::url_safe())
+}
+
+/// Encodes raw binary data into an RG save like [`encode_from_raw`], enciphering it with a different
+/// vigenere key. See [`decode_to_raw_with_key`] for an example.
+pub fn encode_from_raw_with_key(
+    data: &[u8],
+    version: u16,
+    key: &[u8],
+) -> Result<String, SaveError> {
+    if key.is_empty() {
+        return Err(SaveError::EmptyKey);
+    }
+
+    encode(
+        data,
+        version,
+        &Alphabet::default(),
+        key,
+        Container::Zlib,
+        DEFAULT_COMPRESSION_LEVEL,
+    )
+}
+
+/// Encodes raw binary data into an RG save like [`encode_from_raw`], deflating with the given zlib
+/// compression level from 0 (no compression) to 9 (smallest output).
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_raw, encode_from_raw_with_level, SaveError};
+/// let data = vec![0; 1000];
+/// let fast = encode_from_raw_with_level(&data, 0, 0).unwrap();
+/// let small = encode_from_raw_with_level(&data, 0, 9).unwrap();
+///
+/// assert!(small.len() < fast.len());
+/// assert_eq!(decode_to_raw(&fast).unwrap(), data);
+/// assert!(matches!(
+///     encode_from_raw_with_level(&data, 0, 10),
+///     Err(SaveError::InvalidCompressionLevel(10))
+/// ));
+/// ```
+pub fn encode_from_raw_with_level(
+    data: &[u8],
+    version: u16,
+    level: u32,
+) -> Result<String, SaveError> {
+    if level > 9 {
+        return Err(SaveError::InvalidCompressionLevel(level));
+    }
+
+    encode(
+        data,
+        version,
+        &Alphabet::default(),
+        DEFAULT_CIPHER_KEY,
+        Container::Zlib,
+        level,
+    )
+}
+
+/// Encodes raw binary data into an RG save like [`encode_from_raw`], compressing it in the given
+/// container for games which don't use zlib. See [`decode_to_raw_with_container`] for an example.
+pub fn encode_from_raw_with_container(
+    data: &[u8],
+    version: u16,
+    container: Container,
+) -> Result<String, SaveError> {
+    encode(
+        data,
+        version,
+        &Alphabet::default(),
+        DEFAULT_CIPHER_KEY,
+        container,
+        DEFAULT_COMPRESSION_LEVEL,
+    )
+}
+
+/// Encodes raw binary data into a save string
+fn encode(
+    data: &[u8],
+    version: u16,
+    alphabet: &Alphabet,
+    key: &[u8],
+    container: Container,
+    level: u32,
+) -> Result<String, SaveError> {
     // encrypt with vigenere cipher first
-    let data: Vec<u8> = data
-        .iter()
-        .zip(CIPHER_KEY.iter().cycle())
-        .map(|(byte, key)| byte ^ key)
-        .collect();
+    let mut data = data.to_vec();
+    apply_cipher(&mut data, key);
 
-    // then deflate with zlib
-    let mut encoder = ZlibEncoder::new(&data[..], Compression::new(6));
-    let mut out = Vec::new();
-    encoder
-        .read_to_end(&mut out)
-        .map_err(SaveError::CompressError)?;
+    encode_inflated(&data, version, alphabet, container, level)
+}
+
+/// Encodes already enciphered data into a save string
+fn encode_inflated(
+    data: &[u8],
+    version: u16,
+    alphabet: &Alphabet,
+    container: Container,
+    level: u32,
+) -> Result<String, SaveError> {
+    // deflate, with zlib unless told otherwise
+    let out = deflate(data, container, level)?;
 
     // then base64 encoding
-    let data = base64::encode(out);
+    let data = alphabet.encode(&out);
 
     // and finally put in format save expects
     Ok(format!("${version:02}s{data}$e"))
 }
 
+/// Encodes data which is already enciphered into an RG save like [`encode_from_raw`], skipping the
+/// cipher. This is the opposite of [`decode_to_inflated`].
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_inflated, encode_from_inflated};
+/// let save = encode_from_inflated(&[1, 2, 3], 0).unwrap();
+/// assert_eq!(decode_to_inflated(&save).unwrap(), vec![1, 2, 3]);
+/// ```
+pub fn encode_from_inflated(data: &[u8], version: u16) -> Result<String, SaveError> {
+    encode_inflated(
+        data,
+        version,
+        &Alphabet::default(),
+        Container::Zlib,
+        DEFAULT_COMPRESSION_LEVEL,
+    )
+}
+
+/// Re-encodes a save at a different version, keeping its data the same. The data is compressed again
+/// like [`encode_from_raw`], so any other compression settings the save used aren't kept.
+///
+/// # Example
+/// ```
+/// # use savecodec::{decode_to_raw, reencode};
+/// let save = std::fs::read_to_string("../save.txt").unwrap();
+/// let reencoded = reencode(&save, 12).unwrap();
+///
+/// assert!(reencoded.starts_with("$12s"));
+/// assert_eq!(decode_to_raw(&reencoded).unwrap(), decode_to_raw(&save).unwrap());
+/// assert!(reencode(&save, 100).is_err());
+/// ```
+pub fn reencode(save: &str, version: u16) -> Result<String, SaveError> {
+    // versions are written as two digits, so anything larger couldn't be decoded again
+    if version > 99 {
+        return Err(SaveError::InvalidVersion(version.to_string()));
+    }
+
+    encode_from_raw(&decode_to_raw(save)?, version)
+}
+
+/// Checks a save is stable when re-encoded - that decoding it, encoding it again at the same version
+/// and decoding that gives the same data back.
+///
+/// The data is compared rather than the save strings, as compressing again can give a different string
+/// which still decodes to the same data.
+///
+/// # Example
+/// ```
+/// # use savecodec::verify_roundtrip;
+/// let save = std::fs::read_to_string("../save.txt").unwrap();
+/// assert!(verify_roundtrip(&save).unwrap());
+/// assert!(verify_roundtrip("not a save").is_err());
+/// ```
+pub fn verify_roundtrip(save: &str) -> Result<bool, SaveError> {
+    let (version, raw) = decode_to_raw_versioned(save)?;
+    let reencoded = encode_from_raw(&raw, version)?;
+
+    Ok(decode_to_raw(&reencoded)? == raw)
+}
+
 #[format_source("save.format")]
 pub struct Save;
 
 impl Save {
-    pub fn parse_str(save: &str) -> Result<Self, SaveError> {
-        let raw = decode_to_raw(save)?;
+    /// Parses a save string, with any error giving the stage that failed - the wrapper around the save,
+    /// decoding its data, or reading a field of the save from that data. The save string's version is
+    /// passed to the format, where fields can be gated on it as `_version`.
+    ///
+    /// # Example
+    /// ```
+    /// # use savecodec::{decode_to_raw, encode_from_raw, ParseError, Save};
+    /// let save = std::fs::read_to_string("../save.txt").unwrap();
+    /// assert!(Save::parse_str(&save).is_ok());
+    ///
+    /// assert!(matches!(Save::parse_str("not a save"), Err(ParseError::Envelope)));
+    /// assert!(matches!(Save::parse_str("$00s!!!!$e"), Err(ParseError::Decode(_))));
+    ///
+    /// let truncated = encode_from_raw(&decode_to_raw(&save).unwrap()[..2], 0).unwrap();
+    /// assert!(matches!(Save::parse_str(&truncated), Err(ParseError::StructParse { field: "new_field32", offset: 2 })));
+    /// ```
+    pub fn parse_str(save: &str) -> Result<Self, ParseError> {
+        Save::parse_str_versioned(save).map(|(_, save)| save)
+    }
+
+    /// Parses a save like [`Save::parse_str`], also giving the version from the save string
+    fn parse_str_versioned(save: &str) -> Result<(u16, Self), ParseError> {
+        let (version, raw) = decode_to_raw_versioned(save)?;
 
-        Save::read(&mut raw.as_slice()).map_err(SaveError::RWError)
+        Ok((version, Save::read_raw(&raw, version)?))
     }
 
-    pub fn to_str(&self) -> Result<String, SaveError> {
+    /// Reads a save from its decoded data, giving the field which failed to be read if any
+    fn read_raw(raw: &[u8], version: u16) -> Result<Self, ParseError> {
+        Save::read(&mut &raw[..], version).map_err(|error| {
+            let (field, offset) = error
+                .get_ref()
+                .and_then(|error| error.downcast_ref::<SaveFieldError>())
+                .map_or(("unknown", 0), |error| (error.field, error.offset));
+
+            ParseError::StructParse { field, offset }
+        })
+    }
+
+    /// Parses a save string for editing. The fields of the save can be changed through the returned
+    /// [`SaveEdit`], which encodes it back into a save string under the same version it was parsed with.
+    ///
+    /// # Example
+    /// ```
+    /// # use savecodec::{decode_to_raw_versioned, Save};
+    /// let save = std::fs::read_to_string("../save.txt").unwrap();
+    /// let mut edit = Save::edit(&save).unwrap();
+    /// edit.new_field32 += 1000;
+    ///
+    /// let edited = edit.save_to_string().unwrap();
+    /// assert_eq!(decode_to_raw_versioned(&edited).unwrap().0, edit.version());
+    /// assert_eq!(Save::parse_str(&edited).unwrap(), *edit);
+    /// ```
+    pub fn edit(save: &str) -> Result<SaveEdit, ParseError> {
+        let (version, save) = Save::parse_str_versioned(save)?;
+
+        Ok(SaveEdit { save, version })
+    }
+
+    /// Writes the save into raw data, followed by the checksum of that data
+    fn to_raw(&self) -> Result<Vec<u8>, SaveError> {
         let mut raw = Vec::new();
         self.write(&mut raw)?;
 
         let checksum = crc32fast::hash(&raw).to_be_bytes();
         raw.extend_from_slice(&checksum);
 
-        encode_from_raw(&raw, self.save_version)
+        Ok(raw)
+    }
+
+    /// Parses a save like [`Save::parse_str`], first checking the save string's version matches
+    /// [`Save::SCHEMA_VERSION`] so saves in another format aren't parsed as garbage.
+    ///
+    /// # Example
+    /// ```
+    /// # use savecodec::{decode_to_raw, encode_from_raw, ParseError, Save};
+    /// let save = std::fs::read_to_string("../save.txt").unwrap();
+    /// assert!(Save::parse_str_checked(&save).is_ok());
+    ///
+    /// let newer = format!("$99{}", &save[3..]);
+    /// assert!(matches!(
+    ///     Save::parse_str_checked(&newer),
+    ///     Err(ParseError::VersionMismatch { expected: 0, found: 99 })
+    /// ));
+    ///
+    /// let truncated = encode_from_raw(&decode_to_raw(&save).unwrap()[..2], 0).unwrap();
+    /// assert!(matches!(
+    ///     Save::parse_str_checked(&truncated),
+    ///     Err(ParseError::StructParse { field: "new_field32", offset: 2 })
+    /// ));
+    /// ```
+    pub fn parse_str_checked(save: &str) -> Result<Self, ParseError> {
+        let (found, raw) = decode_to_raw_versioned(save)?;
+        if found != Save::SCHEMA_VERSION {
+            return Err(ParseError::VersionMismatch {
+                expected: Save::SCHEMA_VERSION,
+                found,
+            });
+        }
+
+        Save::read_raw(&raw, found)
+    }
+
+    /// Encodes the save back into a save string, under the version it was parsed with. Fields can be
+    /// edited directly in between, giving a full decode, edit, re-encode round trip.
+    ///
+    /// # Example
+    /// ```
+    /// # use savecodec::Save;
+    /// let save = std::fs::read_to_string("../save.txt").unwrap();
+    /// let mut save = Save::parse_str(&save).unwrap();
+    ///
+    /// save.save_version += 1;
+    /// let reencoded = Save::parse_str(&save.to_str().unwrap()).unwrap();
+    /// assert_eq!(reencoded, save);
+    /// ```
+    pub fn to_str(&self) -> Result<String, SaveError> {
+        encode_from_raw(&self.to_raw()?, self.save_version)
+    }
+
+    /// Exports the save as pretty-printed JSON, for editing by hand or with other tools.
+    /// Non-finite floats are written as `null`, so won't survive [`Save::from_json`].
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("save fields always serialise")
+    }
+
+    /// Imports a save previously exported with [`Save::to_json`].
+    ///
+    /// # Example
+    /// ```
+    /// # use savecodec::Save;
+    /// let save = std::fs::read_to_string("../save.txt").unwrap();
+    /// let save = Save::parse_str(&save).unwrap();
+    ///
+    /// let mut edited = Save::from_json(&save.to_json()).unwrap();
+    /// edited.save_version += 1;
+    /// assert!(edited.to_str().is_ok());
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, SaveError> {
+        serde_json::from_str(json).map_err(SaveError::InvalidJson)
+    }
+}
+
+/// A save being edited, from [`Save::edit`]. Its fields can be read and changed through `Deref`, and
+/// [`SaveEdit::save_to_string`] encodes it again.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveEdit {
+    save: Save,
+    version: u16,
+}
+
+impl SaveEdit {
+    /// Version from the save string the save was parsed from
+    pub fn version(&self) -> u16 {
+        self.version
+    }
+
+    /// Gives back the edited save
+    pub fn into_inner(self) -> Save {
+        self.save
+    }
+
+    /// Encodes the edited save back into a save string, under the version of the save string it was
+    /// parsed from. The string is decoded again before it's returned, failing with
+    /// [`SaveError::RoundtripMismatch`] if it doesn't give back exactly the edited data.
+    pub fn save_to_string(&self) -> Result<String, SaveError> {
+        let raw = self.save.to_raw()?;
+        let encoded = encode_from_raw(&raw, self.version)?;
+
+        if decode_to_raw(&encoded)? != raw {
+            return Err(SaveError::RoundtripMismatch);
+        }
+
+        Ok(encoded)
+    }
+}
+
+impl std::ops::Deref for SaveEdit {
+    type Target = Save;
+
+    fn deref(&self) -> &Save {
+        &self.save
+    }
+}
+
+impl std::ops::DerefMut for SaveEdit {
+    fn deref_mut(&mut self) -> &mut Save {
+        &mut self.save
+    }
+}
+
+/// Each stage of decoding and encoding on its own, so the pipeline benchmark measures the same code
+/// the crate runs. Only built with the `bench` feature, and not part of the public API.
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod stages {
+    use super::*;
+
+    /// Decodes the base64 between the save header and footer, detecting the alphabet
+    pub fn decode_base64(payload: &[u8]) -> Result<Vec<u8>, SaveError> {
+        Alphabet::detect(payload).decode(payload)
+    }
+
+    /// Inflates compressed data, detecting the container
+    pub fn inflate(data: &[u8]) -> Result<Vec<u8>, SaveError> {
+        super::inflate(data, Container::detect(data))
+    }
+
+    /// Applies the vigenere cipher with the default key
+    pub fn apply_cipher(data: &mut [u8]) {
+        super::apply_cipher(data, DEFAULT_CIPHER_KEY)
+    }
+
+    /// Deflates data with zlib at the default compression level
+    pub fn deflate(data: &[u8]) -> Result<Vec<u8>, SaveError> {
+        super::deflate(data, Container::Zlib, DEFAULT_COMPRESSION_LEVEL)
+    }
+
+    /// Encodes data as base64 with the standard alphabet
+    pub fn encode_base64(data: &[u8]) -> String {
+        Alphabet::default().encode(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Guards against compressor output changing, which would alter saves byte-for-byte.
+    /// If this fails after a flate2 update, the new output still decodes - see [`encode_from_raw`].
+    #[test]
+    fn encode_golden() {
+        let data: Vec<u8> = (0..512u32).map(|i| (i * 7 % 251) as u8).collect();
+        let encoded = encode_from_raw(&data, 12).unwrap();
+
+        assert_eq!(
+            encoded,
+            concat!(
+                "$12seJwBAAL//XRva2d5QkZcUUwnIT0+FgEVBRvk4P7z0snD39iwo7erhYaCmGh7bnp0QV9aXDIiLzkxChkQ",
+                "FBbj+fz+0MzR29Osv7K2iI2bnmd7dXZySEVAWy0hKiI1ARkXGOzq5+b9z8PUzNejv7G6joSZhGJmeH1rTkhGXiMl",
+                "PT4tBAAaH/Xg6uT4xcff0LOmory5l4KUimFqfnRJVE9ZVSY+OT0tAwwYFuv68fv3wNjb37OtrrqwjZiTlWxpZ3JE",
+                "WkpXUSkiITgMDgsBFOb49Pnzy8TH2q6gtaO2gJ6Wm2pgfWhDRVlSSi0pIT8ABAIfDuXn+/zUz8vH2aKmvLGsh4Gd",
+                "ZXNmcG5GS11VVjUsODIHHRgSDODt//fI287a1KG/urySgo+ZbGF8d3FNTkZRJTUrNDAOAwIZE+/o4PPH29XW0qil",
+                "oLuNgYqCamxicn9JQVpJICQmMykMDgAc4evj/O/Cxtjdy66opr6DhZ2ecGt9cXpSRVFJJyg8OjcWDR8T5Pzn8+/B",
+                "yt7UqbSvubWGnpmddm5zdU1OXVRQKi8lMDoUCBUX7+Dj9vLMycfSpLqqt7GJgoGYaWV2bklNXVNcKCY7KgELBxAI",
+                "6+/j/f7KwN3Io6W5sqqNiYGfZ2F5clFIXF5bMSQ2KAQJAxsU9+r+8MXTxtDOpqu9tbaVjJhpYnp9eVFPUFRSQBf8",
+                "tA==$e"
+            )
+        );
+        assert_eq!(decode_to_raw(&encoded).unwrap(), data);
+    }
+
+    #[test]
+    fn default_cipher_key() {
+        // the first bytes of a decoded save are the key xored with the plaintext, so an all zero
+        // plaintext gives the key back
+        let encoded = encode_from_raw(&[0; 28], 0).unwrap();
+        let mut compressed = Vec::new();
+        ZlibDecoder::new(&base64::decode(&encoded[4..encoded.len() - 2]).unwrap()[..])
+            .read_to_end(&mut compressed)
+            .unwrap();
+
+        assert_eq!(compressed, DEFAULT_CIPHER_KEY.repeat(2));
+        assert_eq!(DEFAULT_CIPHER_KEY, b"therealmisalie");
+    }
+
+    #[test]
+    fn cipher_table_matches_key() {
+        let data: Vec<u8> = (0..1000u32).map(|i| (i * 31 % 256) as u8).collect();
+
+        let mut tabled = data.clone();
+        apply_cipher(&mut tabled, DEFAULT_CIPHER_KEY);
+        let cycled: Vec<u8> = data
+            .iter()
+            .zip(DEFAULT_CIPHER_KEY.iter().cycle())
+            .map(|(byte, key)| byte ^ key)
+            .collect();
+
+        assert_eq!(tabled, cycled);
+        assert!(CIPHER_TABLE_LEN.is_multiple_of(DEFAULT_CIPHER_KEY.len()));
+        #[cfg(feature = "simd")]
+        assert!(CIPHER_TABLE_LEN.is_multiple_of(CIPHER_LANES));
+    }
+
+    #[test]
+    fn reencode_changes_version() {
+        let data: Vec<u8> = (0..200u8).collect();
+        let save = encode_from_raw(&data, 0).unwrap();
+
+        assert_eq!(reencode(&save, 1).unwrap(), format!("$01{}", &save[3..]));
+        assert!(matches!(
+            reencode(&save, 100),
+            Err(SaveError::InvalidVersion(version)) if version == "100"
+        ));
+    }
+
+    #[test]
+    fn decode_gzip_and_zlib() {
+        let data: Vec<u8> = (0..100u8).collect();
+
+        let mut ciphered = data.clone();
+        apply_cipher(&mut ciphered, DEFAULT_CIPHER_KEY);
+        let mut gzipped = Vec::new();
+        flate2::read::GzEncoder::new(&ciphered[..], Compression::default())
+            .read_to_end(&mut gzipped)
+            .unwrap();
+        let gzip_save = format!("$00s{}$e", base64::encode(gzipped));
+
+        assert_eq!(decode_to_raw(&gzip_save).unwrap(), data);
+        assert_eq!(
+            decode_to_raw(&encode_from_raw(&data, 0).unwrap()).unwrap(),
+            data
+        );
+        assert_eq!(decode_with_trailer(&gzip_save).unwrap(), (data, vec![]));
+    }
+
+    #[test]
+    fn custom_alphabet_roundtrip() {
+        let alphabet =
+            Alphabet::new("qwertyuiopasdfghjklzxcvbnm/+QWERTYUIOPASDFGHJKLZXCVBNM9876543210")
+                .unwrap();
+        let data: Vec<u8> = (0..300u32).map(|i| (i * 13 % 256) as u8).collect();
+        let encoded = encode_from_raw_with_alphabet(&data, 3, &alphabet).unwrap();
+
+        assert_ne!(encoded, encode_from_raw(&data, 3).unwrap());
+        assert_eq!(
+            decode_to_raw_with_alphabet(&encoded, &alphabet).unwrap(),
+            data
+        );
+        assert!(matches!(
+            decode_to_raw_with_alphabet("$00s!!!!$e", &alphabet),
+            Err(SaveError::InvalidBase64 { position: 0 })
+        ));
+    }
+
+    #[test]
+    fn json_roundtrip() {
+        let save = std::fs::read_to_string("../save.txt").unwrap();
+        let save = Save::parse_str(&save).unwrap();
+
+        assert_eq!(Save::from_json(&save.to_json()).unwrap(), save);
+        assert!(matches!(
+            Save::from_json("{}"),
+            Err(SaveError::InvalidJson(_))
+        ));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn decode_archive_mixed_batch() {
+        let save = std::fs::read_to_string("../save.txt").unwrap();
+        let saves: Vec<_> = (0..16)
+            .map(|i| match i % 3 {
+                0 => save.clone(),
+                1 => "$00s!!!!$e".to_owned(),
+                _ => encode_from_raw(&[i as u8], 0).unwrap(),
+            })
+            .collect();
+
+        let decoded = decode_archive(&saves);
+        assert_eq!(decoded.len(), saves.len());
+        for (i, result) in decoded.into_iter().enumerate() {
+            match i % 3 {
+                0 => assert_eq!(result.unwrap(), decode_to_raw(&save).unwrap()),
+                1 => assert!(matches!(
+                    result,
+                    Err(SaveError::InvalidBase64 { position: 0 })
+                )),
+                _ => assert_eq!(result.unwrap(), vec![i as u8]),
+            }
+        }
+    }
+
+    #[test]
+    fn valid_saves() {
+        let save = std::fs::read_to_string("../save.txt").unwrap();
+        assert!(is_valid_save(&save));
+        assert!(is_valid_save(&encode_from_raw(&[1, 2, 3], 12).unwrap()));
+
+        // gzip compressed data is valid too
+        let mut gzipped = Vec::new();
+        flate2::read::GzEncoder::new(&b"data"[..], Compression::default())
+            .read_to_end(&mut gzipped)
+            .unwrap();
+        assert!(is_valid_save(&format!("$00s{}$e", base64::encode(gzipped))));
+    }
+
+    #[test]
+    fn invalid_saves() {
+        let save = std::fs::read_to_string("../save.txt").unwrap();
+
+        // wrong wrapper
+        assert!(!is_valid_save(&save[1..]));
+        assert!(!is_valid_save(&format!("{}x", save)));
+        assert!(!is_valid_save("$0seJwrLi0GAAK5AVw=$e"));
+        // bad base64
+        assert!(!is_valid_save("$00seJwrLi0GAAK5AV!=$e"));
+        // valid base64 that isn't compressed
+        assert!(!is_valid_save(&format!(
+            "$00s{}$e",
+            base64::encode(b"plain data")
+        )));
+        // empty
+        assert!(!is_valid_save(""));
+        assert!(!is_valid_save("$00s$e"));
+    }
+
+    #[test]
+    fn decode_versioned() {
+        let data = [1, 2, 3];
+        let save = encode_from_raw(&data, 42).unwrap();
+        assert_eq!(decode_to_raw_versioned(&save).unwrap(), (42, data.to_vec()));
+
+        let save = std::fs::read_to_string("../save.txt").unwrap();
+        let (version, raw) = decode_to_raw_versioned(&save).unwrap();
+        assert_eq!(version, 0);
+        assert_eq!(raw, decode_to_raw(&save).unwrap());
+
+        assert!(matches!(
+            decode_to_raw_versioned("$x0seJwrLi0GAAK5AVw=$e"),
+            Err(SaveError::InvalidVersion(version)) if version == "x0"
+        ));
+        assert!(matches!(
+            decode_to_raw_versioned("$-1seJwrLi0GAAK5AVw=$e"),
+            Err(SaveError::InvalidVersion(version)) if version == "-1"
+        ));
+    }
+
+    #[test]
+    fn custom_cipher_key() {
+        let data: Vec<u8> = (0..100u8).collect();
+        let save = encode_from_raw_with_key(&data, 0, b"another key").unwrap();
+
+        assert_eq!(decode_to_raw_with_key(&save, b"another key").unwrap(), data);
+        assert_ne!(decode_to_raw(&save).unwrap(), data);
+        assert!(matches!(
+            encode_from_raw_with_key(&data, 0, b""),
+            Err(SaveError::EmptyKey)
+        ));
+    }
+
+    #[test]
+    fn decode_reader_in_chunks() {
+        let save = std::fs::read_to_string("../save.txt").unwrap();
+        let mut reader = decode_to_reader(&save).unwrap();
+
+        // reads which don't line up with the key length still need to decipher correctly
+        let mut streamed = Vec::new();
+        let mut chunk = [0; 5];
+        loop {
+            let read = reader.read(&mut chunk).unwrap();
+            if read == 0 {
+                break;
+            }
+            streamed.extend_from_slice(&chunk[..read]);
+        }
+
+        assert_eq!(streamed, decode_to_raw(&save).unwrap());
+    }
+
+    #[test]
+    fn base64_error_positions() {
+        let position = |save: &str| match decode_to_raw(save) {
+            Err(SaveError::InvalidBase64 { position }) => position,
+            other => panic!("expected a base64 error, got {other:?}"),
+        };
+
+        assert_eq!(position("$00seJwrLi!GAAK5AVw=$e"), 6);
+        // truncated data has no invalid byte, so the error is at the end
+        assert_eq!(position("$00seJwrLi0GAAK5A$e"), 13);
+    }
+
+    #[test]
+    fn surrounding_whitespace() {
+        for save in [
+            "$00seJwrLi0GAAK5AVw=$e\n",
+            "$00seJwrLi0GAAK5AVw=$e\r\n",
+            "  $00seJwrLi0GAAK5AVw=$e",
+        ] {
+            assert_eq!(decode_to_raw(save).unwrap(), vec![7, 29, 22]);
+        }
+
+        assert!(matches!(
+            decode_to_raw("$00seJwrLi0G\nAAK5AVw=$e"),
+            Err(SaveError::InvalidSaveString)
+        ));
+    }
+
+    #[test]
+    fn url_safe_roundtrip() {
+        let data: Vec<u8> = (0..=255).collect();
+        let save = encode_from_raw_url_safe(&data, 4).unwrap();
+
+        assert!(!save.contains(['+', '/']));
+        assert_eq!(decode_to_raw(&save).unwrap(), data);
+        assert_eq!(decode_to_raw_versioned(&save).unwrap(), (4, data));
+    }
+
+    /// Encodes every byte value with the URL safe alphabet, so the save is sure to use its symbols
+    fn url_safe_save(key: &[u8], container: Container) -> (Vec<u8>, String) {
+        let data: Vec<u8> = (0..=255).collect();
+        let save = encode(
+            &data,
+            0,
+            &Alphabet::url_safe(),
+            key,
+            container,
+            DEFAULT_COMPRESSION_LEVEL,
+        )
+        .unwrap();
+
+        assert!(save.contains(['-', '_']));
+        (data, save)
+    }
+
+    #[test]
+    fn url_safe_is_valid() {
+        let (_, save) = url_safe_save(DEFAULT_CIPHER_KEY, Container::Zlib);
+        assert!(is_valid_save(&save));
+    }
+
+    #[test]
+    fn url_safe_reader() {
+        let (data, save) = url_safe_save(DEFAULT_CIPHER_KEY, Container::Zlib);
+
+        let mut out = Vec::new();
+        decode_to_reader(&save)
+            .unwrap()
+            .read_to_end(&mut out)
+            .unwrap();
+        assert_eq!(out, data);
+    }
+
+    #[test]
+    fn url_safe_with_key() {
+        let (data, save) = url_safe_save(b"key", Container::Zlib);
+        assert_eq!(decode_to_raw_with_key(&save, b"key").unwrap(), data);
+    }
+
+    #[test]
+    fn url_safe_with_container() {
+        let (data, save) = url_safe_save(DEFAULT_CIPHER_KEY, Container::Gzip);
+        assert_eq!(
+            decode_to_raw_with_container(&save, Container::Gzip).unwrap(),
+            data
+        );
+    }
+
+    #[test]
+    fn url_safe_with_trailer() {
+        let (data, save) = url_safe_save(DEFAULT_CIPHER_KEY, Container::Zlib);
+
+        let mut compressed = Alphabet::url_safe()
+            .decode(&save.as_bytes()[4..save.len() - 2])
+            .unwrap();
+        compressed.extend_from_slice(b"tail");
+        let save = format!("$00s{}$e", Alphabet::url_safe().encode(&compressed));
+
+        assert_eq!(
+            decode_with_trailer(&save).unwrap(),
+            (data, b"tail".to_vec())
+        );
+    }
+
+    #[test]
+    fn url_safe_report() {
+        let (data, save) = url_safe_save(DEFAULT_CIPHER_KEY, Container::Zlib);
+        assert_eq!(decode_with_report(&save).unwrap().0, data);
+    }
+
+    #[test]
+    fn url_safe_parse_checked() {
+        let save = std::fs::read_to_string("../save.txt").unwrap();
+        let raw = decode_to_raw(&save).unwrap();
+        let url_safe = encode_from_raw_url_safe(&raw, 0).unwrap();
+        assert!(url_safe.contains(['-', '_']));
+
+        assert_eq!(
+            Save::parse_str_checked(&url_safe).unwrap(),
+            Save::parse_str(&save).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_alphabets() {
+        let repeated = "AACDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+        let padding = "=BCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+        for symbols in [repeated, padding, "ABC"] {
+            assert!(matches!(
+                Alphabet::new(symbols),
+                Err(SaveError::InvalidAlphabet)
+            ));
+        }
     }
 }